@@ -2,17 +2,27 @@ use std::ops::Deref;
 use std::path::PathBuf;
 
 use async_trait::async_trait;
+use chrono::{Local, Offset};
+use git2::{Oid, Repository};
 use tauri::async_runtime::{block_on, Mutex};
 use tauri::{App, Manager, Runtime, State};
 
-use geeks_tracker_core::domain::task::{Task, TaskCommand, TaskEvent};
-use geeks_tracker_core::eventsourcing::git::{commit_snapshot, GitEventstore};
+use geeks_tracker_core::domain::task::{
+  compensating_command, Task, TaskCommand, TaskEvent, TaskId,
+};
+use geeks_tracker_core::eventsourcing::git::GitEventstore;
 use geeks_tracker_core::eventsourcing::{
-  AggregateRoot, Command, Event, Eventstore, Persisted, Snapshot,
+  replay_aggregate, AggregateRoot, Command, Event, Eventstore, Persisted, Snapshot, Timestamp,
 };
+use geeks_tracker_core::git;
+use geeks_tracker_core::git::get_head;
+use geeks_tracker_core::report::{Report, TaskHistoryEntry};
+use geeks_tracker_core::search::{SearchIndex, SearchQuery};
+use geeks_tracker_core::snapshots::{task_file_commands, TaskSnapshot};
+use geeks_tracker_core::sync::SyncSummary;
+use geeks_tracker_core::workspace::Workspace;
 
-use crate::snapshots::TaskSnapshot;
-use crate::workspace::Workspace;
+use crate::undo::UndoStack;
 
 pub struct Application(Mutex<ApplicationInner>);
 
@@ -28,30 +38,208 @@ impl Deref for Application {
 pub struct ApplicationInner {
   pub workspace_dir: PathBuf,
   pub tasks: AggregateRoot<Task>,
+  pub undo_stack: UndoStack<TaskEvent>,
+  pub search: SearchIndex,
+  head: Oid,
 }
 
 impl ApplicationInner {
   pub async fn new(workspace: &Workspace) -> Result<Self, crate::error::Error> {
-    let tasks = ApplicationInner::init_tasks(workspace).await?;
-    commit_snapshot(&workspace.repo()?)?;
+    let tasks = workspace.load_tasks().await?;
+    workspace.save_snapshot(&tasks).await?;
+    let head = get_head(&workspace.repo()?)?;
+    let search = SearchIndex::build(&tasks);
 
     Ok(Self {
       workspace_dir: workspace.path().to_path_buf(),
       tasks,
+      undo_stack: UndoStack::default(),
+      search,
+      head,
     })
   }
 
-  async fn init_tasks(workspace: &Workspace) -> Result<AggregateRoot<Task>, crate::error::Error> {
-    let eventstore = GitEventstore::new(workspace.path());
-    let snapshot = TaskSnapshot::new(workspace.path()).await?;
+  /// Applies events written by other processes (e.g. cli) since last seen head.
+  pub async fn sync_tasks(&mut self) -> Result<(), crate::error::Error> {
+    let head = get_head(&self.repo()?)?;
+    if head == self.head {
+      return Ok(());
+    }
+    let eventstore = GitEventstore::new(&self.workspace_dir);
+    let events = eventstore.read_after(self.head).await?;
+    self.tasks.save_events(events.clone())?;
+    self.search.apply(&events, &self.tasks);
+    self.head = head;
+    self.save_task_files(&events).await
+  }
+
+  /// Syncs workspace with its remote and reloads tasks.
+  /// History may be rewritten, so undo entries are dropped.
+  pub async fn sync_remote(&mut self) -> Result<SyncSummary, crate::error::Error> {
+    let workspace = Workspace::open(&self.workspace_dir)?;
+    let summary = workspace.sync().await?;
+    self.tasks = workspace.load_tasks().await?;
+    TaskSnapshot::new(&self.workspace_dir)
+      .await?
+      .save(&self.tasks)
+      .await?;
+    self.search = SearchIndex::build(&self.tasks);
+    self.undo_stack = UndoStack::default();
+    self.head = get_head(&self.repo()?)?;
+    Ok(summary)
+  }
+
+  /// Reverts last `count` groups of events, returns compensating events.
+  /// Events of groups done before a failure are committed, so they are returned with it.
+  pub async fn undo(
+    &mut self,
+    count: usize,
+  ) -> (Vec<Persisted<TaskEvent>>, Result<(), crate::error::Error>) {
+    let mut reverted = Vec::new();
+    for _ in 0..count {
+      let Some(events) = self.undo_stack.pop_undo() else {
+        break;
+      };
+      let compensated = match self.compensate(&events).await {
+        Ok(compensated) => compensated,
+        Err(e) => {
+          // keeps the entry, so it can be tried again after the conflict is resolved.
+          self.undo_stack.push_undo(events);
+          return (reverted, Err(e));
+        }
+      };
+      reverted.extend(compensated.iter().cloned());
+      self.undo_stack.push_redo(compensated);
+    }
+    (reverted, Ok(()))
+  }
+
+  /// Re-applies last `count` undone groups, returns compensating events.
+  /// Events of groups done before a failure are committed, so they are returned with it.
+  pub async fn redo(
+    &mut self,
+    count: usize,
+  ) -> (Vec<Persisted<TaskEvent>>, Result<(), crate::error::Error>) {
+    let mut reverted = Vec::new();
+    for _ in 0..count {
+      let Some(events) = self.undo_stack.pop_redo() else {
+        break;
+      };
+      let compensated = match self.compensate(&events).await {
+        Ok(compensated) => compensated,
+        Err(e) => {
+          // keeps the entry, so it can be tried again after the conflict is resolved.
+          self.undo_stack.push_redo(events);
+          return (reverted, Err(e));
+        }
+      };
+      reverted.extend(compensated.iter().cloned());
+      self.undo_stack.push_undo(compensated);
+    }
+    (reverted, Ok(()))
+  }
+
+  /// Issues compensating commands of `events` in reverse order.
+  /// Before-state of each event is rebuilt from history, so deleted tasks can be restored.
+  async fn compensate(
+    &mut self,
+    events: &[Persisted<TaskEvent>],
+  ) -> Result<Vec<Persisted<TaskEvent>>, crate::error::Error> {
+    let eventstore = GitEventstore::new(&self.workspace_dir);
+    let mut commands = Vec::new();
+    for persisted in events.iter().rev() {
+      let before: Option<Task> =
+        replay_aggregate(&eventstore, &persisted.aggregate_id, persisted.version - 1).await?;
+      if let Some(command) = compensating_command(before.as_ref(), &persisted.event, &self.tasks) {
+        commands.push(command);
+      }
+    }
+    self.handle_commands(commands).await
+  }
+
+  /// Executes commands and persists their events in one commit.
+  /// Nothing is applied when any of them fails.
+  pub async fn handle_commands(
+    &mut self,
+    commands: Vec<TaskCommand>,
+  ) -> Result<Vec<Persisted<TaskEvent>>, crate::error::Error> {
+    self.sync_tasks().await?;
+    let mut tasks = self.tasks.clone();
+    let events = commands
+      .into_iter()
+      .map(|command| tasks.execute_command(command))
+      .collect::<Result<Vec<_>, _>>()?;
+    let eventstore = GitEventstore::new(&self.workspace_dir);
+    eventstore.append(events.clone()).await?;
+    self.tasks = tasks;
+    self.search.apply(&events, &self.tasks);
+    self.head = get_head(&self.repo()?)?;
+    self.save_task_files(&events).await?;
+    Ok(events)
+  }
+
+  /// Writes files of the tasks `events` changed, so files edited by hand start from current
+  /// tasks. Files of other tasks are left alone, as they may be edited right now.
+  async fn save_task_files(
+    &self,
+    events: &[Persisted<TaskEvent>],
+  ) -> Result<(), crate::error::Error> {
+    TaskSnapshot::new(&self.workspace_dir)
+      .await?
+      .save_tasks(&self.tasks, events.iter().map(|x| x.aggregate_id.as_str()))
+      .await?;
+    Ok(())
+  }
+
+  /// Applies task file `name` edited outside the app, `contents` is `None` when removed.
+  pub async fn apply_task_file(
+    &mut self,
+    name: &str,
+    contents: Option<&str>,
+  ) -> Result<Vec<Persisted<TaskEvent>>, crate::error::Error> {
+    self.sync_tasks().await?;
+    let commands = task_file_commands(&self.tasks, name, contents)?;
+    if commands.is_empty() {
+      return Ok(Vec::new());
+    }
+    let events = self.handle_commands(commands).await?;
+    // a task created from a note is written to its own file, which takes the note's place.
+    TaskSnapshot::new(&self.workspace_dir)
+      .await?
+      .remove_new_file(&self.tasks, name)
+      .await?;
+    Ok(events)
+  }
+
+  /// Reports task activity between `from` and `to`, grouped by local days.
+  pub fn report(&self, from: Timestamp, to: Timestamp) -> Result<Report, crate::error::Error> {
+    let offset = Local::now().offset().fix();
+    let report = Report::read(&self.repo()?, from, to, offset)?;
+    Ok(report)
+  }
 
-    let mut root = snapshot.load().await?;
-    let unsaved_events = eventstore.read_until_snapshot().await?;
+  /// Events of the task with their commits, oldest first.
+  pub fn task_history(&self, id: TaskId) -> Result<Vec<TaskHistoryEntry>, crate::error::Error> {
+    let id = id.to_string();
+    let history = TaskHistoryEntry::read(&self.repo()?, self.tasks.resolve_id(&id))?;
+    Ok(history)
+  }
 
-    root.save_events(unsaved_events)?;
-    snapshot.save(&root).await?;
+  /// Tasks matching the query, ranked by relevance.
+  pub fn search_tasks(&self, query: &str) -> Result<Vec<&Task>, crate::error::Error> {
+    let query = SearchQuery::parse(query, &Local::now().offset().fix())?;
+    let tasks = self
+      .search
+      .search(&query)
+      .into_iter()
+      .filter_map(|x| self.tasks.get_state(x.id.to_string()))
+      .collect();
+    Ok(tasks)
+  }
 
-    Ok(root)
+  fn repo(&self) -> Result<Repository, git::Error> {
+    let repo = Repository::open(&self.workspace_dir)?;
+    Ok(repo)
   }
 }
 
@@ -70,9 +258,13 @@ impl CommandHandler<TaskCommand, TaskEvent> for ApplicationInner {
     &mut self,
     command: TaskCommand,
   ) -> Result<Persisted<TaskEvent>, crate::error::Error> {
+    self.sync_tasks().await?;
     let persisted = self.tasks.execute_command(command)?;
     let eventstore = GitEventstore::new(&self.workspace_dir);
     eventstore.append(vec![persisted.clone()]).await?;
+    self.search.apply(&[persisted.clone()], &self.tasks);
+    self.head = get_head(&self.repo()?)?;
+    self.save_task_files(&[persisted.clone()]).await?;
     Ok(persisted)
   }
 }
@@ -84,3 +276,117 @@ pub fn setup_application<R: Runtime>(app: &mut App<R>) -> Result<(), crate::erro
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use geeks_tracker_testing::git::FixtureRepository;
+
+  use super::*;
+
+  fn create(title: &str) -> TaskCommand {
+    TaskCommand::Create {
+      title: title.to_string(),
+      status: None,
+      schedule: None,
+    }
+  }
+
+  #[tokio::test]
+  async fn keep_undo_entry_when_compensation_fails() {
+    let fixture = FixtureRepository::default();
+    let persisted = AggregateRoot::<Task>::default()
+      .execute_command(create("Hello"))
+      .unwrap();
+    GitEventstore::new(fixture.path())
+      .append(vec![persisted])
+      .await
+      .unwrap();
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    let mut application = ApplicationInner::new(&workspace).await.unwrap();
+
+    let events = application
+      .handle_commands(vec![create("World")])
+      .await
+      .unwrap();
+    application.undo_stack.push(events);
+    // deleted by another process, e.g. cli, so it is not in the undo stack.
+    application
+      .handle_commands(vec![TaskCommand::Delete { id: TaskId::new(2) }])
+      .await
+      .unwrap();
+
+    assert!(application.undo(1).await.1.is_err());
+    assert!(application.undo_stack.can_undo());
+    assert!(!application.undo_stack.can_redo());
+  }
+
+  #[tokio::test]
+  async fn return_undone_events_before_failure() {
+    let fixture = FixtureRepository::default();
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    let mut application = ApplicationInner::new(&workspace).await.unwrap();
+    application
+      .handle_commands(vec![create("Hello")])
+      .await
+      .unwrap();
+    let created = application
+      .handle_commands(vec![create("World")])
+      .await
+      .unwrap();
+    application.undo_stack.push(created);
+    let updated = application
+      .handle_commands(vec![TaskCommand::UpdateTitle {
+        id: TaskId::new(1),
+        title: "Hello again".to_string(),
+      }])
+      .await
+      .unwrap();
+    application.undo_stack.push(updated);
+    // deleted by another process, so only the title change can be undone.
+    application
+      .handle_commands(vec![TaskCommand::Delete { id: TaskId::new(2) }])
+      .await
+      .unwrap();
+
+    let (events, result) = application.undo(2).await;
+    assert!(result.is_err());
+    assert_eq!(events.len(), 1);
+    assert_eq!(application.tasks.get_state("#1").unwrap().title, "Hello");
+    assert!(application.undo_stack.can_undo());
+    assert!(application.undo_stack.can_redo());
+  }
+
+  #[tokio::test]
+  async fn keep_file_edited_while_another_task_changes() {
+    let fixture = FixtureRepository::default();
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    let mut application = ApplicationInner::new(&workspace).await.unwrap();
+    application
+      .handle_commands(vec![create("Hello"), create("World")])
+      .await
+      .unwrap();
+
+    // not applied yet, as the watcher waits for more changes.
+    let path = workspace.path().join("tasks/#1.md");
+    let edited = std::fs::read_to_string(&path)
+      .unwrap()
+      .replace("## Title\nHello", "## Title\nHello again");
+    std::fs::write(&path, &edited).unwrap();
+    application
+      .handle_command(TaskCommand::UpdateTitle {
+        id: TaskId::new(2),
+        title: "World again".to_string(),
+      })
+      .await
+      .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), edited);
+
+    application
+      .apply_task_file("#1.md", Some(&edited))
+      .await
+      .unwrap();
+    let title = |no: &str| application.tasks.get_state(no).unwrap().title.to_owned();
+    assert_eq!(title("#1"), "Hello again");
+    assert_eq!(title("#2"), "World again");
+  }
+}
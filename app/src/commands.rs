@@ -1,21 +1,47 @@
+use serde::Serialize;
 use tauri::State;
 
 use geeks_tracker_core::dispatch::DispatchMessage;
-use geeks_tracker_core::domain::task::{Task, TaskCommand};
+use geeks_tracker_core::domain::query::TaskQuery;
+use geeks_tracker_core::domain::task::{ChecklistProgress, Task, TaskCommand, TaskId};
+use geeks_tracker_core::eventsourcing::Timestamp;
+use geeks_tracker_core::report::{ReportFormat, TaskHistoryEntry};
+use geeks_tracker_core::sync::SyncSummary;
 
 use crate::application::{Application, CommandHandler};
 use crate::dispatcher::Dispatcher;
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListedTask {
+  #[serde(flatten)]
+  pub task: Task,
+  pub progress: Option<ChecklistProgress>,
+}
+
 #[tauri::command]
-pub async fn list_tasks(application: State<'_, Application>) -> Result<Vec<Task>, ()> {
+pub async fn list_tasks(
+  application: State<'_, Application>,
+  query: Option<TaskQuery>,
+  search: Option<String>,
+) -> Result<Vec<ListedTask>, crate::error::Error> {
   log::trace!("tauri command: list_tasks");
-  let tasks: Vec<_> = application
-    .lock()
-    .await
-    .tasks
-    .states
-    .values()
-    .cloned()
+  let mut application = application.lock().await;
+  if let Err(e) = application.sync_tasks().await {
+    log::error!("fail to sync tasks: {:?}", e);
+  }
+  let query = query.unwrap_or_default();
+  // ranked search results keep their order unless query sorts them.
+  let found = match &search {
+    Some(search) => query.apply(application.search_tasks(search)?),
+    None => query.run(&application.tasks),
+  };
+  let tasks: Vec<_> = found
+    .into_iter()
+    .map(|x| ListedTask {
+      task: x.clone(),
+      progress: x.checklist_progress(),
+    })
     .collect();
   Ok(tasks)
 }
@@ -27,11 +53,99 @@ pub async fn run_task_command(
   command: TaskCommand,
 ) -> Result<(), crate::error::Error> {
   log::trace!("tauri command: run_task_command");
-  let persisted = application.lock().await.handle_command(command).await?;
+  let mut application = application.lock().await;
+  let persisted = application.handle_command(command).await?;
+  application.undo_stack.push(vec![persisted.clone()]);
   let _ = dispatcher
     .send(DispatchMessage::TaskPersisted {
       events: vec![persisted],
+      can_undo: application.undo_stack.can_undo(),
+      can_redo: application.undo_stack.can_redo(),
     })
     .await;
   Ok(())
 }
+
+#[tauri::command]
+pub async fn undo_task(
+  application: State<'_, Application>,
+  dispatcher: State<'_, Dispatcher>,
+  count: Option<usize>,
+) -> Result<(), crate::error::Error> {
+  log::trace!("tauri command: undo_task");
+  let mut application = application.lock().await;
+  // groups done before a failure are committed, so they are dispatched anyway.
+  let (events, result) = application.undo(count.unwrap_or(1)).await;
+  let _ = dispatcher
+    .send(DispatchMessage::TaskUndone {
+      events,
+      can_undo: application.undo_stack.can_undo(),
+      can_redo: application.undo_stack.can_redo(),
+    })
+    .await;
+  result
+}
+
+#[tauri::command]
+pub async fn redo_task(
+  application: State<'_, Application>,
+  dispatcher: State<'_, Dispatcher>,
+  count: Option<usize>,
+) -> Result<(), crate::error::Error> {
+  log::trace!("tauri command: redo_task");
+  let mut application = application.lock().await;
+  // groups done before a failure are committed, so they are dispatched anyway.
+  let (events, result) = application.redo(count.unwrap_or(1)).await;
+  let _ = dispatcher
+    .send(DispatchMessage::TaskRedone {
+      events,
+      can_undo: application.undo_stack.can_undo(),
+      can_redo: application.undo_stack.can_redo(),
+    })
+    .await;
+  result
+}
+
+#[tauri::command]
+pub async fn sync_workspace(
+  application: State<'_, Application>,
+  dispatcher: State<'_, Dispatcher>,
+) -> Result<SyncSummary, crate::error::Error> {
+  log::trace!("tauri command: sync_workspace");
+  let mut application = application.lock().await;
+  let summary = application.sync_remote().await?;
+  let _ = dispatcher
+    .send(DispatchMessage::WorkspaceSynced {
+      summary: summary.clone(),
+      can_undo: application.undo_stack.can_undo(),
+      can_redo: application.undo_stack.can_redo(),
+    })
+    .await;
+  Ok(summary)
+}
+
+#[tauri::command]
+pub async fn generate_report(
+  application: State<'_, Application>,
+  from: Timestamp,
+  to: Timestamp,
+  format: Option<ReportFormat>,
+) -> Result<String, crate::error::Error> {
+  log::trace!("tauri command: generate_report");
+  let report = application.lock().await.report(from, to)?;
+  let rendered = report.render(format.unwrap_or(ReportFormat::Markdown))?;
+  Ok(rendered)
+}
+
+#[tauri::command]
+pub async fn task_history(
+  application: State<'_, Application>,
+  id: TaskId,
+) -> Result<Vec<TaskHistoryEntry>, crate::error::Error> {
+  log::trace!("tauri command: task_history");
+  let mut application = application.lock().await;
+  if let Err(e) = application.sync_tasks().await {
+    log::error!("fail to sync tasks: {:?}", e);
+  }
+  application.task_history(id)
+}
@@ -1,3 +1,5 @@
+use geeks_tracker_core::workspace::LoadTasksError;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
   #[error(transparent)]
@@ -9,9 +11,29 @@ pub enum Error {
   #[error(transparent)]
   SerdeJson(#[from] serde_json::Error),
   #[error(transparent)]
-  SerdeYaml(#[from] serde_yaml::Error),
+  Eventstore(#[from] geeks_tracker_core::eventsourcing::git::GitEventstoreError),
+  #[error(transparent)]
+  Snapshot(#[from] geeks_tracker_core::snapshots::Error),
+  #[error(transparent)]
+  LoadTasks(#[from] LoadTasksError),
+  #[error(transparent)]
+  Replay(
+    #[from]
+    geeks_tracker_core::eventsourcing::ReplayError<
+      geeks_tracker_core::domain::Error,
+      geeks_tracker_core::eventsourcing::git::GitEventstoreError,
+    >,
+  ),
+  #[error(transparent)]
+  Report(#[from] geeks_tracker_core::report::Error),
+  #[error(transparent)]
+  Search(#[from] geeks_tracker_core::search::Error),
+  #[error(transparent)]
+  Sync(#[from] geeks_tracker_core::sync::Error),
   #[error(transparent)]
   Domain(#[from] geeks_tracker_core::domain::Error),
+  #[error(transparent)]
+  Watch(#[from] notify_debouncer_mini::notify::Error),
 }
 
 impl serde::Serialize for Error {
@@ -4,10 +4,14 @@
 use tauri::{generate_handler, CustomMenuItem, SystemTray, SystemTrayMenu};
 
 use crate::application::setup_application;
-use crate::commands::{list_tasks, run_task_command};
+use crate::commands::{
+  generate_report, list_tasks, redo_task, run_task_command, sync_workspace, task_history,
+  undo_task,
+};
 use crate::dispatcher::setup_dispatcher;
 use crate::global_shortcut::setup_global_shortcut;
 use crate::schedule::setup_schedule;
+use crate::watch::setup_watcher;
 use crate::win::setup_main_window;
 use crate::workspace::setup_workspace;
 
@@ -18,8 +22,9 @@ mod error;
 mod global_shortcut;
 mod patches;
 mod schedule;
-mod snapshots;
+mod undo;
 mod utils;
+mod watch;
 mod win;
 mod workspace;
 
@@ -44,9 +49,18 @@ fn main() {
       setup_main_window(app).expect("fail to setup main window");
       setup_global_shortcut(app).expect("fail to setup global shortcut");
       setup_schedule(app);
+      setup_watcher(app).expect("fail to setup watcher");
       Ok(())
     })
-    .invoke_handler(generate_handler![list_tasks, run_task_command])
+    .invoke_handler(generate_handler![
+      list_tasks,
+      run_task_command,
+      undo_task,
+      redo_task,
+      sync_workspace,
+      generate_report,
+      task_history
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
@@ -1,10 +1,12 @@
+use chrono::{Local, Offset, Utc};
 use geeks_tracker_core::dispatch::DispatchMessage;
 use tauri::api::notification::Notification;
 use tauri::async_runtime::spawn;
-use tauri::{App, Manager, Runtime};
+use tauri::{App, AppHandle, Manager, Runtime};
 use tokio_schedule::{every, Job};
 
-use geeks_tracker_core::domain::task::TaskCommand;
+use geeks_tracker_core::domain::task::{Task, TaskCommand, TaskEvent};
+use geeks_tracker_core::eventsourcing::Persisted;
 
 use crate::application::{Application, CommandHandler};
 use crate::dispatcher::Dispatcher;
@@ -12,57 +14,104 @@ use crate::dispatcher::Dispatcher;
 pub fn setup_schedule<R: Runtime>(app: &mut App<R>) {
   let handle = app.handle();
   spawn(async move {
-    every(1)
-      .second()
-      .perform(|| async {
-        let application = handle.state::<Application>();
-        let dispatcher = handle.state::<Dispatcher>();
-        let tasks = application
-          .lock()
-          .await
-          .tasks
-          .states
-          .values()
-          .filter(|&x| x.schedule_available())
-          .cloned()
-          .collect::<Vec<_>>();
-        for task in tasks {
-          if let Some(schedule) = task.schedule {
-            let prev = task.status.to_owned();
-            let next = schedule.status.to_owned();
-            let e1 = application
-              .lock()
-              .await
-              .handle_command(TaskCommand::UpdateStatus {
-                id: task.id.to_owned(),
-                status: schedule.status,
-              })
-              .await;
-            let e2 = application
-              .lock()
-              .await
-              .handle_command(TaskCommand::UpdateSchedule {
-                id: task.id,
-                schedule: None,
-              })
-              .await;
-            if let (Ok(e1), Ok(e2)) = (e1, e2) {
-              let _ = Notification::new("me.seokju.geeks-tracker")
-                .title(format!("{} {} -> {}", task.id, prev, next))
-                .body(task.title)
-                .show();
-              let _ = dispatcher
-                .send(DispatchMessage::TaskPersisted {
-                  events: vec![e1, e2],
-                })
-                .await;
-              log::trace!("notify: {:?}", task.id);
-            } else {
-              log::error!("schedule error");
-            }
-          }
-        }
+    // catch up schedules missed while app was not running.
+    fire_schedules(&handle).await;
+    every(1).second().perform(|| fire_schedules(&handle)).await;
+  });
+}
+
+/// Fires due schedules in order of their time, and notifies them at once.
+async fn fire_schedules<R: Runtime>(handle: &AppHandle<R>) {
+  let application = handle.state::<Application>();
+  let dispatcher = handle.state::<Dispatcher>();
+  let mut tasks = {
+    let application = application.lock().await;
+    application
+      .tasks
+      .states
+      .values()
+      .filter(|&x| x.schedule_available())
+      .map(|x| {
+        let version = application.tasks.get_version(x.id.to_string()).cloned();
+        (x.clone(), version)
+      })
+      .collect::<Vec<_>>()
+  };
+  tasks.sort_by_key(|(task, _)| task.schedule.as_ref().map(|x| x.at));
+
+  let mut fired = Vec::new();
+  for (task, version) in tasks {
+    let Some(schedule) = &task.schedule else {
+      continue;
+    };
+    // recurring schedule moves to next occurrence instead of being cleared.
+    let now = Utc::now().timestamp_millis();
+    let next = schedule.next(now, &Local::now().offset().fix());
+    // skip when task is changed after read, not to overwrite the change.
+    let result = application
+      .lock()
+      .await
+      .handle_command(TaskCommand::FireSchedule {
+        id: task.id.to_owned(),
+        next,
+        expected_version: version,
       })
       .await;
-  });
+    match result {
+      Ok(persisted) => fired.push((task, persisted)),
+      Err(e) => log::warn!("skip schedule of {}: {}", task.id, e),
+    }
+  }
+  if fired.is_empty() {
+    return;
+  }
+
+  notify(&fired);
+  let application = application.lock().await;
+  let _ = dispatcher
+    .send(DispatchMessage::TaskPersisted {
+      events: fired.into_iter().map(|(_, x)| x).collect(),
+      can_undo: application.undo_stack.can_undo(),
+      can_redo: application.undo_stack.can_redo(),
+    })
+    .await;
+}
+
+fn notify(fired: &[(Task, Persisted<TaskEvent>)]) {
+  let (title, body) = match fired {
+    [(task, persisted)] => {
+      let next = match &persisted.event {
+        TaskEvent::ScheduleFired { status, .. } => status.to_string(),
+        _ => String::new(),
+      };
+      (
+        format!("{} {} -> {}", task.id, task.status, next),
+        task.title.to_owned(),
+      )
+    }
+    _ => {
+      let overdue = fired
+        .iter()
+        .filter(|(_, x)| match &x.event {
+          TaskEvent::ScheduleFired { scheduled_at, .. } => x.occurred_at - scheduled_at > 60 * 1000,
+          _ => false,
+        })
+        .count();
+      let title = match overdue {
+        0 => format!("{} schedules fired", fired.len()),
+        _ => format!("{} schedules fired ({} overdue)", fired.len(), overdue),
+      };
+      let body = fired
+        .iter()
+        .map(|(task, _)| format!("{} {}", task.id, task.title))
+        .collect::<Vec<_>>()
+        .join("\n");
+      (title, body)
+    }
+  };
+  let _ = Notification::new("me.seokju.geeks-tracker")
+    .title(title)
+    .body(body)
+    .show();
+  log::trace!("notify: {} schedules", fired.len());
 }
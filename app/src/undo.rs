@@ -0,0 +1,117 @@
+use geeks_tracker_core::eventsourcing::{Event, Persisted};
+
+const MAX_ENTRIES: usize = 100;
+
+/// Groups of persisted events, which can be reverted by compensating commands.
+/// Each group is events from one command (e.g. `run_task_command`), reverted together.
+#[derive(Debug, Clone)]
+pub struct UndoStack<E>
+where
+  E: Event,
+{
+  undo: Vec<Vec<Persisted<E>>>,
+  redo: Vec<Vec<Persisted<E>>>,
+}
+
+impl<E> Default for UndoStack<E>
+where
+  E: Event,
+{
+  fn default() -> Self {
+    Self {
+      undo: Vec::new(),
+      redo: Vec::new(),
+    }
+  }
+}
+
+impl<E> UndoStack<E>
+where
+  E: Event,
+{
+  /// Records events of new command. Redo is not available anymore after this.
+  pub fn push(&mut self, events: Vec<Persisted<E>>) {
+    self.redo.clear();
+    self.push_undo(events);
+  }
+
+  pub fn push_undo(&mut self, events: Vec<Persisted<E>>) {
+    if events.is_empty() {
+      return;
+    }
+    self.undo.push(events);
+    if self.undo.len() > MAX_ENTRIES {
+      self.undo.remove(0);
+    }
+  }
+
+  pub fn push_redo(&mut self, events: Vec<Persisted<E>>) {
+    if events.is_empty() {
+      return;
+    }
+    self.redo.push(events);
+  }
+
+  pub fn pop_undo(&mut self) -> Option<Vec<Persisted<E>>> {
+    self.undo.pop()
+  }
+
+  pub fn pop_redo(&mut self) -> Option<Vec<Persisted<E>>> {
+    self.redo.pop()
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use geeks_tracker_core::eventsourcing::dummy::TodoEvent;
+
+  use super::*;
+
+  fn events(version: u64) -> Vec<Persisted<TodoEvent>> {
+    vec![Persisted {
+      aggregate_id: "todo1".to_string(),
+      version,
+      occurred_at: 0,
+      event: TodoEvent::TodoTitleUpdated {
+        title: "Eat pizza".to_string(),
+      },
+    }]
+  }
+
+  #[test]
+  fn redo_until_new_push() {
+    let mut stack = UndoStack::<TodoEvent>::default();
+    stack.push(events(1));
+    stack.push(events(2));
+    let undone = stack.pop_undo().unwrap();
+    assert_eq!(undone[0].version, 2);
+    stack.push_redo(events(3));
+    assert!(stack.can_undo());
+    assert!(stack.can_redo());
+
+    stack.push(events(4));
+    assert!(!stack.can_redo());
+    assert!(stack.pop_redo().is_none());
+  }
+
+  #[test]
+  fn keep_limited_entries() {
+    let mut stack = UndoStack::<TodoEvent>::default();
+    for version in 0..(MAX_ENTRIES as u64 + 10) {
+      stack.push(events(version));
+    }
+    let mut count = 0;
+    while stack.pop_undo().is_some() {
+      count += 1;
+    }
+    assert_eq!(count, MAX_ENTRIES);
+  }
+}
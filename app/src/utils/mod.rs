@@ -1,5 +1,3 @@
-pub use frontmatter::*;
 pub use win::*;
 
-mod frontmatter;
 mod win;
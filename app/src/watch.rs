@@ -0,0 +1,87 @@
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use tauri::async_runtime::spawn;
+use tauri::{App, AppHandle, Manager, Runtime};
+use tokio::fs;
+use tokio::sync::mpsc;
+
+use geeks_tracker_core::dispatch::DispatchMessage;
+use geeks_tracker_core::workspace::Workspace;
+
+use crate::application::Application;
+use crate::dispatcher::Dispatcher;
+
+/// Watches task files, and applies changes made outside the app (e.g. by an editor) as commands.
+///
+/// The workspace dir is watched instead of the tasks dir, which is swapped with a new one when
+/// the snapshot is replaced and would no longer be watched after that.
+pub fn setup_watcher<R: Runtime>(app: &mut App<R>) -> Result<(), crate::error::Error> {
+  let workspace_dir = app.state::<Workspace>().path().to_path_buf();
+  // events come with canonical paths on some platforms, e.g. `/private/var` on macOS.
+  let workspace_dir = std::fs::canonicalize(&workspace_dir).unwrap_or(workspace_dir);
+  let dir = workspace_dir.join("tasks");
+  let (tx, mut rx) = mpsc::channel::<DebounceEventResult>(100);
+  let mut debouncer = new_debouncer(Duration::from_millis(500), move |result| {
+    let _ = tx.blocking_send(result);
+  })?;
+  debouncer
+    .watcher()
+    .watch(&workspace_dir, RecursiveMode::Recursive)?;
+
+  let handle = app.handle();
+  spawn(async move {
+    // stops watching when dropped.
+    let _debouncer = debouncer;
+    while let Some(result) = rx.recv().await {
+      match result {
+        Ok(events) => {
+          let files = events
+            .into_iter()
+            .filter(|x| x.path.parent() == Some(dir.as_path()));
+          for event in files {
+            apply_task_file(&handle, &event.path).await;
+          }
+        }
+        Err(e) => log::warn!("fail to watch task files: {}", e),
+      }
+    }
+  });
+  Ok(())
+}
+
+async fn apply_task_file<R: Runtime>(handle: &AppHandle<R>, path: &Path) {
+  let Some(name) = path.file_name().and_then(|x| x.to_str()) else {
+    return;
+  };
+  let contents = match fs::read_to_string(path).await {
+    Ok(contents) => Some(contents),
+    Err(e) if e.kind() == ErrorKind::NotFound => None,
+    Err(e) => {
+      log::warn!("fail to read {}: {}", name, e);
+      return;
+    }
+  };
+
+  let application = handle.state::<Application>();
+  let mut application = application.lock().await;
+  let result = application.apply_task_file(name, contents.as_deref()).await;
+  match result {
+    Ok(events) if events.is_empty() => {}
+    Ok(events) => {
+      log::trace!("apply {}: {} events", name, events.len());
+      let dispatcher = handle.state::<Dispatcher>();
+      let _ = dispatcher
+        .send(DispatchMessage::TaskPersisted {
+          events,
+          can_undo: application.undo_stack.can_undo(),
+          can_redo: application.undo_stack.can_redo(),
+        })
+        .await;
+    }
+    Err(e) => log::warn!("skip {}: {}", name, e),
+  }
+}
@@ -0,0 +1,597 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use chrono::{Local, Offset, TimeZone, Utc};
+use git2::Repository;
+
+use geeks_tracker_core::domain::query::TaskQuery;
+use geeks_tracker_core::domain::task::{
+  blockers, blocking_tasks, Task, TaskCommand, TaskEvent, TaskId, TaskSchedule, TaskStatus,
+};
+use geeks_tracker_core::eventsourcing::git::{GitEventstore, EVENT_MSG};
+use geeks_tracker_core::eventsourcing::{
+  AggregateRoot, Command, Event, Eventstore, Persisted, Snapshot, Timestamp,
+};
+use geeks_tracker_core::git::CommitReader;
+use geeks_tracker_core::language::{parse_task_command, Duration};
+use geeks_tracker_core::search::{SearchIndex, SearchQuery};
+use geeks_tracker_core::snapshots::TaskSnapshot;
+use geeks_tracker_core::sync::SyncStatus;
+use geeks_tracker_core::workspace::Workspace;
+
+use crate::error::Error;
+
+/// Changes of `set` command, only given ones are applied.
+#[derive(Debug, Default)]
+pub struct TaskChanges {
+  pub title: Option<String>,
+  pub body: Option<String>,
+  pub status: Option<TaskStatus>,
+  pub after: Option<Duration>,
+  /// Starts the task even if its blockers are not done.
+  pub force: bool,
+}
+
+pub struct Session {
+  workspace: Workspace,
+  tasks: AggregateRoot<Task>,
+}
+
+impl Session {
+  pub async fn open(workspace: Workspace) -> Result<Self, Error> {
+    let tasks = workspace.load_tasks().await?;
+    Ok(Self { workspace, tasks })
+  }
+
+  pub fn list(
+    &self,
+    out: &mut impl Write,
+    status: &[TaskStatus],
+    tag: Option<&str>,
+  ) -> Result<(), Error> {
+    let query = TaskQuery {
+      status: (!status.is_empty()).then(|| status.to_vec()),
+      tag: tag.map(|x| x.to_string()),
+      ..Default::default()
+    };
+    for task in query.run(&self.tasks) {
+      writeln!(
+        out,
+        "{:<6} {:<12} {}",
+        task.id.to_string(),
+        task.status.to_string(),
+        task.title
+      )?;
+    }
+    Ok(())
+  }
+
+  pub fn search(&self, out: &mut impl Write, query: &str) -> Result<(), Error> {
+    let query = SearchQuery::parse(query, &Local::now().offset().fix())?;
+    for hit in SearchIndex::build(&self.tasks).search(&query) {
+      let task = self.get_task(hit.id)?;
+      writeln!(
+        out,
+        "{:<6} {:<12} {}",
+        task.id.to_string(),
+        task.status.to_string(),
+        task.title
+      )?;
+    }
+    Ok(())
+  }
+
+  pub fn show(&self, out: &mut impl Write, id: TaskId) -> Result<(), Error> {
+    let task = self.get_task(id)?;
+    let version = self.tasks.get_version(task.id.to_string()).unwrap_or(&0);
+    writeln!(out, "{} {}", task.id, task.title)?;
+    writeln!(out, "status:     {}", task.status)?;
+    writeln!(out, "version:    {}", version)?;
+    if !task.tags.is_empty() {
+      writeln!(out, "tags:       {}", task.tags.join(", "))?;
+    }
+    writeln!(out, "created at: {}", format_millis(task.created_at))?;
+    writeln!(out, "updated at: {}", format_millis(task.updated_at))?;
+    if !task.sessions.is_empty() {
+      let tracked = task.tracked_duration(Utc::now().timestamp_millis());
+      writeln!(
+        out,
+        "tracked:    {} ({} sessions)",
+        format_duration(tracked),
+        task.sessions.len()
+      )?;
+    }
+    if let Some(schedule) = &task.schedule {
+      write!(
+        out,
+        "schedule:   {} at {}",
+        schedule.status,
+        format_millis(schedule.at)
+      )?;
+      match &schedule.repeat {
+        Some(repeat) => writeln!(out, ", {}", repeat)?,
+        None => writeln!(out)?,
+      }
+    }
+    let blockers = blockers(task, &self.tasks);
+    if !blockers.is_empty() {
+      writeln!(out, "blocked by: {}", join_ids(&blockers))?;
+    }
+    let blocking = blocking_tasks(task, &self.tasks);
+    if !blocking.is_empty() {
+      writeln!(out, "blocks:     {}", join_ids(&blocking))?;
+    }
+    if let Some(body) = &task.body {
+      writeln!(out)?;
+      writeln!(out, "{}", body)?;
+    }
+    Ok(())
+  }
+
+  pub async fn new_task(
+    &mut self,
+    out: &mut impl Write,
+    title: String,
+    status: Option<TaskStatus>,
+    after: Option<Duration>,
+  ) -> Result<(), Error> {
+    let command = match (status, after) {
+      (Some(status), Some(after)) => TaskCommand::Create {
+        title,
+        status: None,
+        schedule: Some(schedule_after(status, after)),
+      },
+      (status, _) => TaskCommand::Create {
+        title,
+        status,
+        schedule: None,
+      },
+    };
+    self.execute(out, vec![command]).await
+  }
+
+  pub async fn set(
+    &mut self,
+    out: &mut impl Write,
+    id: TaskId,
+    changes: TaskChanges,
+  ) -> Result<(), Error> {
+    self.get_task(id)?;
+    let mut commands = vec![];
+    if let Some(title) = changes.title {
+      commands.push(TaskCommand::UpdateTitle { id, title });
+    }
+    if let Some(body) = changes.body {
+      commands.push(TaskCommand::UpdateBody {
+        id,
+        body: Some(body),
+      });
+    }
+    match (changes.status, changes.after) {
+      (Some(status), Some(after)) => commands.push(TaskCommand::UpdateSchedule {
+        id,
+        schedule: Some(schedule_after(status, after)),
+      }),
+      (Some(status), None) => commands.push(TaskCommand::UpdateStatus {
+        id,
+        status,
+        force: changes.force,
+      }),
+      _ => {}
+    };
+    if commands.is_empty() {
+      return Err(Error::NothingToUpdate);
+    }
+    self.execute(out, commands).await
+  }
+
+  pub async fn tag(
+    &mut self,
+    out: &mut impl Write,
+    id: TaskId,
+    tags: Vec<String>,
+    remove: bool,
+  ) -> Result<(), Error> {
+    self.get_task(id)?;
+    let commands = tags
+      .into_iter()
+      .map(|tag| match remove {
+        true => TaskCommand::RemoveTag { id, tag },
+        false => TaskCommand::AddTag { id, tag },
+      })
+      .collect();
+    self.execute(out, commands).await
+  }
+
+  pub async fn block(
+    &mut self,
+    out: &mut impl Write,
+    id: TaskId,
+    blockers: Vec<TaskId>,
+    remove: bool,
+  ) -> Result<(), Error> {
+    self.get_task(id)?;
+    let commands = blockers
+      .into_iter()
+      .map(|blocker| match remove {
+        true => TaskCommand::RemoveBlocker { id, blocker },
+        false => TaskCommand::AddBlocker { id, blocker },
+      })
+      .collect();
+    self.execute(out, commands).await
+  }
+
+  pub async fn delete(&mut self, out: &mut impl Write, id: TaskId) -> Result<(), Error> {
+    self.get_task(id)?;
+    self.execute(out, vec![TaskCommand::Delete { id }]).await
+  }
+
+  pub async fn run(&mut self, out: &mut impl Write, line: &str) -> Result<(), Error> {
+    let offset = Local::now().offset().fix();
+    let command = parse_task_command(line, Utc::now().timestamp_millis(), offset)?;
+    self.execute(out, vec![command]).await
+  }
+
+  pub fn log(
+    &self,
+    out: &mut impl Write,
+    id: Option<TaskId>,
+    limit: Option<usize>,
+  ) -> Result<(), Error> {
+    let repo =
+      Repository::open(self.workspace.path()).map_err(geeks_tracker_core::git::Error::from)?;
+    // events of deleted tasks can't be found by number, unless keyed by it.
+    let aggregate_id = id.map(|x| self.tasks.resolve_id(&x.to_string()).to_owned());
+    let commits = CommitReader::new(&repo)?
+      .start_on_head()
+      .flatten()
+      .filter(|x| x.message.subject.contains(EVENT_MSG))
+      .flat_map(|x| {
+        let time = x.time;
+        let oid = x.id;
+        match GitEventstore::<TaskEvent>::commit_to_events(x) {
+          Ok(events) => events
+            .into_iter()
+            .rev()
+            .map(|persisted| Ok((oid, time, persisted)))
+            .collect(),
+          Err(e) => vec![Err(e)],
+        }
+      })
+      .filter(|x| match (x, &aggregate_id) {
+        (Ok((_, _, persisted)), Some(id)) => &persisted.aggregate_id == id,
+        _ => true,
+      })
+      .take(limit.unwrap_or(usize::MAX));
+
+    for commit in commits {
+      let (oid, time, persisted) = commit?;
+      let oid = oid.to_string();
+      writeln!(
+        out,
+        "{} {} {:<6} v{:<3} {}",
+        &oid[..7],
+        format_seconds(time),
+        self.display_id(&persisted.aggregate_id),
+        persisted.version,
+        persisted.event.name()
+      )?;
+    }
+    Ok(())
+  }
+
+  pub fn remote(&self, out: &mut impl Write, url: &str) -> Result<(), Error> {
+    self.workspace.set_remote(url)?;
+    writeln!(out, "remote: {}", url)?;
+    Ok(())
+  }
+
+  pub async fn sync(&mut self, out: &mut impl Write) -> Result<(), Error> {
+    let summary = self.workspace.sync().await?;
+    let status = match summary.status {
+      SyncStatus::UpToDate => "up to date",
+      SyncStatus::Pushed => "pushed",
+      SyncStatus::Pulled => "pulled",
+      SyncStatus::Merged => "merged",
+    };
+    writeln!(out, "{}", status)?;
+    for renamed in &summary.renamed {
+      writeln!(out, "renamed {} -> {}", renamed.from, renamed.to)?;
+    }
+    if summary.dropped > 0 {
+      writeln!(out, "dropped {} conflicting events", summary.dropped)?;
+    }
+    self.tasks = self.workspace.load_tasks().await?;
+    // pulled files may be behind the events, they are committed on next sync.
+    TaskSnapshot::new(self.workspace.path())
+      .await?
+      .save(&self.tasks)
+      .await?;
+    Ok(())
+  }
+
+  pub async fn verify(&mut self, out: &mut impl Write, repair: bool) -> Result<(), Error> {
+    let diffs = self.workspace.verify().await?;
+    if diffs.is_empty() {
+      writeln!(out, "ok")?;
+      return Ok(());
+    }
+    if repair {
+      // tasks missing from snapshot are known by number only after repair.
+      self.tasks = self.workspace.repair().await?;
+    }
+    for diff in &diffs {
+      writeln!(out, "{:<6} {}", self.display_id(diff.id()), diff)?;
+    }
+    let tasks = diffs.iter().map(|x| x.id()).collect::<HashSet<_>>().len();
+    match repair {
+      true => writeln!(out, "repaired {} tasks", tasks)?,
+      false => writeln!(out, "run with --repair to regenerate task files")?,
+    }
+    Ok(())
+  }
+
+  /// Number of the task when it exists, or the aggregate id.
+  fn display_id(&self, aggregate_id: &str) -> String {
+    self
+      .tasks
+      .get_state(aggregate_id)
+      .map_or_else(|| aggregate_id.to_string(), |x| x.id.to_string())
+  }
+
+  fn get_task(&self, id: TaskId) -> Result<&Task, Error> {
+    self
+      .tasks
+      .get_state(id.to_string())
+      .ok_or_else(|| Error::TaskNotFound(id.to_string()))
+  }
+
+  /// Executes commands and persists their events in one commit.
+  /// Nothing is written when any of them fails.
+  async fn execute(
+    &mut self,
+    out: &mut impl Write,
+    commands: Vec<TaskCommand>,
+  ) -> Result<(), Error> {
+    let mut tasks = self.tasks.clone();
+    let mut targets = Vec::with_capacity(commands.len());
+    let mut events = Vec::with_capacity(commands.len());
+    for command in commands {
+      targets.push(command.aggregate_id());
+      events.push(tasks.execute_command(command)?);
+    }
+    let eventstore = GitEventstore::new(self.workspace.path());
+    eventstore.append(events.clone()).await?;
+    self.tasks = tasks;
+    // only changed tasks, files are committed with the next snapshot.
+    TaskSnapshot::new(self.workspace.path())
+      .await?
+      .save_tasks(&self.tasks, events.iter().map(|x| x.aggregate_id.as_str()))
+      .await?;
+    for (target, persisted) in targets.into_iter().zip(&events) {
+      let id = target.unwrap_or_else(|| self.display_id(&persisted.aggregate_id));
+      print_persisted(out, &id, persisted)?;
+    }
+    Ok(())
+  }
+}
+
+fn join_ids(ids: &[TaskId]) -> String {
+  ids
+    .iter()
+    .map(|x| x.to_string())
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+fn schedule_after(status: TaskStatus, after: Duration) -> TaskSchedule {
+  TaskSchedule {
+    at: Utc::now().timestamp_millis() + after.as_millis(),
+    status,
+    repeat: None,
+  }
+}
+
+fn print_persisted(
+  out: &mut impl Write,
+  id: &str,
+  persisted: &Persisted<TaskEvent>,
+) -> Result<(), Error> {
+  writeln!(
+    out,
+    "{} {} (v{})",
+    id,
+    persisted.event.name(),
+    persisted.version
+  )?;
+  Ok(())
+}
+
+fn format_millis(millis: Timestamp) -> String {
+  match Local.timestamp_millis_opt(millis).single() {
+    Some(x) => x.format("%Y-%m-%d %H:%M:%S").to_string(),
+    None => millis.to_string(),
+  }
+}
+
+fn format_duration(millis: Timestamp) -> String {
+  let minutes = millis / 1000 / 60;
+  format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+fn format_seconds(seconds: i64) -> String {
+  format_millis(seconds * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+  use geeks_tracker_testing::git::FixtureRepository;
+
+  use super::*;
+
+  async fn session(fixture: &FixtureRepository) -> Session {
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    Session::open(workspace).await.unwrap()
+  }
+
+  fn output(buf: Vec<u8>) -> String {
+    String::from_utf8(buf).unwrap()
+  }
+
+  #[tokio::test]
+  async fn create_and_list_tasks() {
+    let fixture = FixtureRepository::default();
+    let mut out = vec![];
+    let mut s = session(&fixture).await;
+    s.new_task(&mut out, "Write docs".to_string(), None, None)
+      .await
+      .unwrap();
+    s.run(&mut out, r#"new "Fix bug" status in_progress"#)
+      .await
+      .unwrap();
+    assert_eq!(output(out), "#1 task.created (v1)\n#2 task.created (v1)\n");
+
+    // events are loaded again on another session.
+    let s = session(&fixture).await;
+    let mut out = vec![];
+    s.list(&mut out, &[TaskStatus::InProgress], None).unwrap();
+    assert_eq!(output(out), "#2     in_progress  Fix bug\n");
+  }
+
+  #[tokio::test]
+  async fn list_tasks_by_tag() {
+    let fixture = FixtureRepository::default();
+    let mut out = vec![];
+    let mut s = session(&fixture).await;
+    s.new_task(&mut out, "Write docs".to_string(), None, None)
+      .await
+      .unwrap();
+    s.new_task(&mut out, "Fix bug".to_string(), None, None)
+      .await
+      .unwrap();
+    s.tag(&mut out, TaskId::new(2), vec!["work".to_string()], false)
+      .await
+      .unwrap();
+
+    let mut out = vec![];
+    s.list(&mut out, &[], Some("work")).unwrap();
+    assert_eq!(output(out), "#2     backlog      Fix bug\n");
+  }
+
+  #[tokio::test]
+  async fn search_tasks() {
+    let fixture = FixtureRepository::default();
+    let mut out = vec![];
+    let mut s = session(&fixture).await;
+    s.run(&mut out, r#"new "Write docs""#).await.unwrap();
+    s.run(&mut out, r#"new "Deploy app" status in_progress"#)
+      .await
+      .unwrap();
+    let changes = TaskChanges {
+      body: Some("Deploy guide".to_string()),
+      ..Default::default()
+    };
+    s.set(&mut out, TaskId::new(1), changes).await.unwrap();
+
+    // title matches rank first.
+    let mut out = vec![];
+    s.search(&mut out, "deploy").unwrap();
+    assert_eq!(
+      output(out),
+      "#2     in_progress  Deploy app\n#1     backlog      Write docs\n"
+    );
+    let mut out = vec![];
+    s.search(&mut out, r#"status:backlog "deploy guide""#)
+      .unwrap();
+    assert_eq!(output(out), "#1     backlog      Write docs\n");
+  }
+
+  #[tokio::test]
+  async fn set_and_log_task() {
+    let fixture = FixtureRepository::default();
+    let mut out = vec![];
+    let mut s = session(&fixture).await;
+    s.new_task(&mut out, "Write docs".to_string(), None, None)
+      .await
+      .unwrap();
+    s.set(
+      &mut out,
+      TaskId::new(1),
+      TaskChanges {
+        title: Some("Write more docs".to_string()),
+        status: Some(TaskStatus::Done),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+    assert_eq!(s.get_task(TaskId::new(1)).unwrap().status, TaskStatus::Done);
+
+    let mut out = vec![];
+    s.log(&mut out, Some(TaskId::new(1)), None).unwrap();
+    let lines = output(out);
+    let lines = lines.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].ends_with("task.statusUpdated"));
+    assert!(lines[2].ends_with("task.created"));
+  }
+
+  #[tokio::test]
+  async fn verify_and_repair_task_files() {
+    let fixture = FixtureRepository::default();
+    let mut out = vec![];
+    let mut s = session(&fixture).await;
+    s.run(&mut out, r#"new "Write docs""#).await.unwrap();
+    s.run(&mut out, r#"new "Fix bug""#).await.unwrap();
+    s.workspace.save_snapshot(&s.tasks).await.unwrap();
+    std::fs::remove_file(fixture.path().join("tasks/#2.md")).unwrap();
+
+    let mut s = session(&fixture).await;
+    let mut out = vec![];
+    s.verify(&mut out, true).await.unwrap();
+    assert_eq!(
+      output(out),
+      "#2     snapshot is missing\nrepaired 1 tasks\n"
+    );
+    let mut out = vec![];
+    s.verify(&mut out, false).await.unwrap();
+    assert_eq!(output(out), "ok\n");
+  }
+
+  #[tokio::test]
+  async fn write_nothing_when_a_command_fails() {
+    let fixture = FixtureRepository::default();
+    let mut out = vec![];
+    let mut s = session(&fixture).await;
+    s.run(&mut out, r#"new "Write docs""#).await.unwrap();
+    s.run(&mut out, r#"new "Fix bug""#).await.unwrap();
+    s.block(&mut out, TaskId::new(2), vec![TaskId::new(1)], false)
+      .await
+      .unwrap();
+
+    let changes = TaskChanges {
+      title: Some("Fix more bugs".to_string()),
+      status: Some(TaskStatus::InProgress),
+      ..Default::default()
+    };
+    let err = s.set(&mut out, TaskId::new(2), changes).await.unwrap_err();
+    assert!(err.to_string().contains("#1"), "{}", err);
+    assert_eq!(s.get_task(TaskId::new(2)).unwrap().title, "Fix bug");
+
+    // title is not written either.
+    let s = session(&fixture).await;
+    assert_eq!(s.get_task(TaskId::new(2)).unwrap().title, "Fix bug");
+    let mut out = vec![];
+    s.log(&mut out, Some(TaskId::new(2)), None).unwrap();
+    assert_eq!(output(out).lines().count(), 2);
+  }
+
+  #[tokio::test]
+  async fn error_when_task_not_exists() {
+    let fixture = FixtureRepository::default();
+    let mut out = vec![];
+    let mut s = session(&fixture).await;
+    let err = s.delete(&mut out, TaskId::new(3)).await.unwrap_err();
+    assert!(matches!(err, Error::TaskNotFound(_)));
+  }
+}
@@ -0,0 +1,31 @@
+use geeks_tracker_core::workspace::LoadTasksError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+  #[error(transparent)]
+  Git(#[from] geeks_tracker_core::git::Error),
+  #[error(transparent)]
+  Eventstore(#[from] geeks_tracker_core::eventsourcing::git::GitEventstoreError),
+  #[error(transparent)]
+  Io(#[from] std::io::Error),
+  #[error(transparent)]
+  SerdeJson(#[from] serde_json::Error),
+  #[error(transparent)]
+  LoadTasks(#[from] LoadTasksError),
+  #[error(transparent)]
+  Snapshot(#[from] geeks_tracker_core::snapshots::Error),
+  #[error(transparent)]
+  Domain(#[from] geeks_tracker_core::domain::Error),
+  #[error(transparent)]
+  Parse(#[from] geeks_tracker_core::language::ParseError),
+  #[error(transparent)]
+  Search(#[from] geeks_tracker_core::search::Error),
+  #[error(transparent)]
+  Sync(#[from] geeks_tracker_core::sync::Error),
+  #[error("task not found: {0}")]
+  TaskNotFound(String),
+  #[error("nothing to update")]
+  NothingToUpdate,
+  #[error("data dir not found, give workspace dir with --workspace")]
+  NoDataDir,
+}
@@ -0,0 +1,225 @@
+use std::io::stdout;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use geeks_tracker_core::domain::task::{TaskId, TaskStatus};
+use geeks_tracker_core::language::{parse_duration, Duration};
+use geeks_tracker_core::workspace::Workspace;
+
+use crate::commands::{Session, TaskChanges};
+
+mod commands;
+mod error;
+
+/// Same identifier with desktop app, so both share the workspace repository.
+const APP_IDENTIFIER: &str = "me.seokju.geeks-tracker";
+
+#[derive(Debug, Parser)]
+#[command(
+  name = "geeks-tracker",
+  about = "Track tasks in geeks-tracker workspace"
+)]
+struct Cli {
+  /// Workspace directory. Defaults to the one desktop app uses.
+  #[arg(long, global = true, env = "GEEKS_TRACKER_WORKSPACE")]
+  workspace: Option<PathBuf>,
+
+  #[command(subcommand)]
+  command: CliCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum CliCommand {
+  /// List tasks
+  List {
+    /// Show only tasks in status (repeatable)
+    #[arg(long, short, value_parser = parse_status)]
+    status: Vec<TaskStatus>,
+    /// Show only tasks having tag
+    #[arg(long)]
+    tag: Option<String>,
+  },
+  /// Search tasks, e.g. `status:in_progress "deploy" created:>2024-05-01`
+  Search { query: String },
+  /// Show a task
+  Show {
+    #[arg(value_parser = parse_task_id)]
+    id: TaskId,
+  },
+  /// Create a task
+  New {
+    title: String,
+    #[arg(long, short, value_parser = parse_status)]
+    status: Option<TaskStatus>,
+    /// Schedule status change after duration instead of changing now (e.g. "30m")
+    #[arg(long, requires = "status", value_parser = parse_duration_arg)]
+    after: Option<Duration>,
+  },
+  /// Update a task
+  Set {
+    #[arg(value_parser = parse_task_id)]
+    id: TaskId,
+    #[arg(long, short)]
+    title: Option<String>,
+    #[arg(long, short)]
+    body: Option<String>,
+    #[arg(long, short, value_parser = parse_status)]
+    status: Option<TaskStatus>,
+    /// Schedule status change after duration instead of changing now (e.g. "30m")
+    #[arg(long, requires = "status", value_parser = parse_duration_arg)]
+    after: Option<Duration>,
+    /// Start the task even if its blockers are not done
+    #[arg(long, short, requires = "status")]
+    force: bool,
+  },
+  /// Add tags to a task, or remove them with `--remove`
+  Tag {
+    #[arg(value_parser = parse_task_id)]
+    id: TaskId,
+    #[arg(required = true)]
+    tags: Vec<String>,
+    #[arg(long, short)]
+    remove: bool,
+  },
+  /// Mark a task blocked by other tasks, or unmark with `--remove`
+  Block {
+    #[arg(value_parser = parse_task_id)]
+    id: TaskId,
+    #[arg(required = true, value_parser = parse_task_id)]
+    blockers: Vec<TaskId>,
+    #[arg(long, short)]
+    remove: bool,
+  },
+  /// Delete a task
+  Delete {
+    #[arg(value_parser = parse_task_id)]
+    id: TaskId,
+  },
+  /// Show event log, optionally for a task
+  Log {
+    #[arg(value_parser = parse_task_id)]
+    id: Option<TaskId>,
+    /// Maximum number of events to show
+    #[arg(long, short = 'n')]
+    limit: Option<usize>,
+  },
+  /// Run a line of command language (e.g. 'set #12 status done')
+  Run { line: Vec<String> },
+  /// Set url of the remote repository to sync with
+  Remote { url: String },
+  /// Pull and push tasks with the remote
+  Sync,
+  /// Check task files against the event log
+  Verify {
+    /// Regenerate task files from events
+    #[arg(long)]
+    repair: bool,
+  },
+}
+
+fn parse_task_id(value: &str) -> Result<TaskId, String> {
+  let no = value.strip_prefix('#').unwrap_or(value);
+  no.parse::<i32>()
+    .map(TaskId::new)
+    .map_err(|_| format!("invalid task id: {}", value))
+}
+
+fn parse_status(value: &str) -> Result<TaskStatus, String> {
+  value
+    .parse::<TaskStatus>()
+    .map_err(|_| "expected one of backlog, queue, in_progress, done".to_string())
+}
+
+fn parse_duration_arg(value: &str) -> Result<Duration, String> {
+  parse_duration(value).map_err(|e| e.to_string())
+}
+
+fn workspace_dir(cli: &Cli) -> Result<PathBuf, crate::error::Error> {
+  match &cli.workspace {
+    Some(dir) => Ok(dir.to_owned()),
+    None => dirs_next::data_dir()
+      .map(|x| x.join(APP_IDENTIFIER).join("workspace"))
+      .ok_or(crate::error::Error::NoDataDir),
+  }
+}
+
+async fn run(cli: Cli) -> Result<(), crate::error::Error> {
+  let workspace = Workspace::open(&workspace_dir(&cli)?)?;
+  let mut session = Session::open(workspace).await?;
+  let mut out = stdout().lock();
+
+  match cli.command {
+    CliCommand::List { status, tag } => session.list(&mut out, &status, tag.as_deref()),
+    CliCommand::Search { query } => session.search(&mut out, &query),
+    CliCommand::Show { id } => session.show(&mut out, id),
+    CliCommand::New {
+      title,
+      status,
+      after,
+    } => session.new_task(&mut out, title, status, after).await,
+    CliCommand::Set {
+      id,
+      title,
+      body,
+      status,
+      after,
+      force,
+    } => {
+      let changes = TaskChanges {
+        title,
+        body,
+        status,
+        after,
+        force,
+      };
+      session.set(&mut out, id, changes).await
+    }
+    CliCommand::Tag { id, tags, remove } => session.tag(&mut out, id, tags, remove).await,
+    CliCommand::Block {
+      id,
+      blockers,
+      remove,
+    } => session.block(&mut out, id, blockers, remove).await,
+    CliCommand::Delete { id } => session.delete(&mut out, id).await,
+    CliCommand::Log { id, limit } => session.log(&mut out, id, limit),
+    CliCommand::Run { line } => session.run(&mut out, &line.join(" ")).await,
+    CliCommand::Remote { url } => session.remote(&mut out, &url),
+    CliCommand::Sync => session.sync(&mut out).await,
+    CliCommand::Verify { repair } => session.verify(&mut out, repair).await,
+  }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  let cli = Cli::parse();
+  match run(cli).await {
+    Ok(_) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("error: {}", e);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use clap::CommandFactory;
+
+  use super::*;
+
+  #[test]
+  fn verify_cli() {
+    Cli::command().debug_assert();
+  }
+
+  #[test]
+  fn parse_args() {
+    assert_eq!(parse_task_id("#12").unwrap(), TaskId::new(12));
+    assert_eq!(parse_task_id("12").unwrap(), TaskId::new(12));
+    assert!(parse_task_id("#").is_err());
+    assert_eq!(parse_status("in_progress").unwrap(), TaskStatus::InProgress);
+    assert!(parse_status("doing").is_err());
+  }
+}
@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+
+use geeks_tracker_core::eventsourcing::dummy::{Todo, TodoEvent, TodoStatus};
+use geeks_tracker_core::eventsourcing::git::GitEventstore;
+use geeks_tracker_core::eventsourcing::{
+  get_unsaved_events, AggregateRoot, Eventstore, Persisted, VersionSelect,
+};
+use geeks_tracker_testing::git::FixtureRepository;
+
+const AGGREGATES: u64 = 100;
+
+fn events(count: u64) -> Vec<Persisted<TodoEvent>> {
+  (0..count)
+    .map(|i| {
+      let id = format!("todo{}", i % AGGREGATES);
+      let version = i / AGGREGATES + 1;
+      let event = match version {
+        1 => TodoEvent::TodoCreated {
+          id: id.to_owned(),
+          title: "Drink coffee".to_string(),
+          status: TodoStatus::Todo,
+        },
+        _ => TodoEvent::TodoTitleUpdated {
+          title: format!("Drink coffee {}", version),
+        },
+      };
+      Persisted {
+        aggregate_id: id,
+        version,
+        occurred_at: 0,
+        event,
+      }
+    })
+    .collect()
+}
+
+fn fixture(rt: &Runtime, count: u64) -> FixtureRepository {
+  let fixture = FixtureRepository::default();
+  let eventstore = GitEventstore::new(fixture.path());
+  // one commit per event, as commands are usually appended one by one.
+  rt.block_on(async {
+    for persisted in events(count) {
+      eventstore.append(vec![persisted]).await.unwrap();
+    }
+  });
+  fixture
+}
+
+fn bench_read(c: &mut Criterion) {
+  let rt = Runtime::new().unwrap();
+  let mut group = c.benchmark_group("eventstore");
+  group.sample_size(10);
+
+  for count in [1000, 5000] {
+    let fixture = fixture(&rt, count);
+    let eventstore = GitEventstore::<TodoEvent>::new(fixture.path());
+
+    group.bench_with_input(BenchmarkId::new("read", count), &count, |b, _| {
+      b.iter(|| {
+        rt.block_on(eventstore.read("todo42".to_string(), VersionSelect::All))
+          .unwrap()
+      })
+    });
+
+    // every aggregate is one version behind, like loading from a stale snapshot.
+    let mut root = AggregateRoot::<Todo>::default();
+    let behind = events(count)
+      .into_iter()
+      .filter(|x| x.version < count / AGGREGATES)
+      .collect();
+    root.save_events(behind).unwrap();
+    group.bench_with_input(
+      BenchmarkId::new("get_unsaved_events", count),
+      &count,
+      |b, _| b.iter(|| rt.block_on(get_unsaved_events(&root, &eventstore)).unwrap()),
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_read);
+criterion_main!(benches);
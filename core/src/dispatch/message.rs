@@ -3,11 +3,34 @@ use typeshare::typeshare;
 
 use crate::domain::task::TaskEvent;
 use crate::eventsourcing::Persisted;
+use crate::sync::SyncSummary;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "name", content = "data")]
 #[typeshare]
 pub enum DispatchMessage {
   #[serde(rename = "task.persisted", rename_all = "camelCase")]
-  TaskPersisted { events: Vec<Persisted<TaskEvent>> },
+  TaskPersisted {
+    events: Vec<Persisted<TaskEvent>>,
+    can_undo: bool,
+    can_redo: bool,
+  },
+  #[serde(rename = "task.undone", rename_all = "camelCase")]
+  TaskUndone {
+    events: Vec<Persisted<TaskEvent>>,
+    can_undo: bool,
+    can_redo: bool,
+  },
+  #[serde(rename = "task.redone", rename_all = "camelCase")]
+  TaskRedone {
+    events: Vec<Persisted<TaskEvent>>,
+    can_undo: bool,
+    can_redo: bool,
+  },
+  #[serde(rename = "workspace.synced", rename_all = "camelCase")]
+  WorkspaceSynced {
+    summary: SyncSummary,
+    can_undo: bool,
+    can_redo: bool,
+  },
 }
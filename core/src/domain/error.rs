@@ -1,9 +1,39 @@
+use crate::eventsourcing::VersionMismatch;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
   #[error("invalid task id")]
   InvalidTaskId,
+  #[error("invalid task status")]
+  InvalidTaskStatus,
   #[error("task not exists")]
   TaskNotExists,
   #[error("task already exists")]
   TaskAlreadyExists,
+  #[error("invalid tag")]
+  InvalidTag,
+  #[error("tag already exists")]
+  TagAlreadyExists,
+  #[error("tag not exists")]
+  TagNotExists,
+  #[error("invalid weekday")]
+  InvalidWeekday,
+  #[error("schedule not exists")]
+  ScheduleNotExists,
+  #[error("schedule is not due")]
+  ScheduleNotDue,
+  #[error("blocker already exists")]
+  BlockerAlreadyExists,
+  #[error("blocker not exists")]
+  BlockerNotExists,
+  #[error("dependency cycle")]
+  DependencyCycle,
+  #[error("invalid checklist item")]
+  InvalidChecklistItem,
+  #[error("checklist item not exists")]
+  ChecklistItemNotExists,
+  #[error("blocked by {0}")]
+  BlockedBy(crate::domain::task::TaskId),
+  #[error(transparent)]
+  VersionMismatch(#[from] VersionMismatch),
 }
@@ -1,4 +1,6 @@
 pub use error::*;
 
 mod error;
+pub mod query;
+pub mod recurrence;
 pub mod task;
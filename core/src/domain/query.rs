@@ -0,0 +1,228 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::domain::task::{Task, TaskStatus};
+use crate::eventsourcing::{AggregateRoot, Timestamp};
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+#[typeshare]
+pub enum TaskSortKey {
+  #[default]
+  Id,
+  Title,
+  Status,
+  CreatedAt,
+  UpdatedAt,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+#[typeshare]
+pub enum SortDirection {
+  #[default]
+  Asc,
+  Desc,
+}
+
+/// Filter, sort and page of tasks. Every given condition should match.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase", default)]
+#[typeshare]
+pub struct TaskQuery {
+  /// Any of the statuses.
+  pub status: Option<Vec<TaskStatus>>,
+  /// Case-insensitive match in title or body.
+  pub text: Option<String>,
+  pub tag: Option<String>,
+  /// `from` inclusive, `to` exclusive.
+  pub created_from: Option<Timestamp>,
+  pub created_to: Option<Timestamp>,
+  pub updated_from: Option<Timestamp>,
+  pub updated_to: Option<Timestamp>,
+  pub has_schedule: Option<bool>,
+  pub sort: Option<TaskSortKey>,
+  pub direction: Option<SortDirection>,
+  pub limit: Option<u32>,
+  pub offset: Option<u32>,
+}
+
+impl TaskQuery {
+  pub fn matches(&self, task: &Task) -> bool {
+    let in_range = |time: Timestamp, from: Option<Timestamp>, to: Option<Timestamp>| {
+      from.map_or(true, |x| x <= time) && to.map_or(true, |x| time < x)
+    };
+    self
+      .status
+      .as_ref()
+      .map_or(true, |x| x.contains(&task.status))
+      && self.text.as_ref().map_or(true, |x| contains_text(task, x))
+      && self.tag.as_ref().map_or(true, |x| task.has_tag(x))
+      && in_range(task.created_at, self.created_from, self.created_to)
+      && in_range(task.updated_at, self.updated_from, self.updated_to)
+      && self
+        .has_schedule
+        .map_or(true, |x| task.schedule.is_some() == x)
+  }
+
+  /// Matching tasks of the root, in order of `sort` and then id.
+  pub fn run<'a>(&self, root: &'a AggregateRoot<Task>) -> Vec<&'a Task> {
+    let mut tasks: Vec<_> = root.states.values().collect();
+    tasks.sort_by_key(|x| x.id.no);
+    self.apply(tasks)
+  }
+
+  /// Filters and pages tasks. Given order is kept unless `sort` is set, e.g. for ranked results.
+  pub fn apply<'a>(&self, tasks: Vec<&'a Task>) -> Vec<&'a Task> {
+    let mut tasks: Vec<_> = tasks.into_iter().filter(|x| self.matches(x)).collect();
+    if let Some(sort) = self.sort {
+      // stable, so ties keep the given order.
+      tasks.sort_by(|a, b| {
+        let ordering = compare(a, b, sort);
+        match self.direction.unwrap_or_default() {
+          SortDirection::Asc => ordering,
+          SortDirection::Desc => ordering.reverse(),
+        }
+      });
+    }
+    tasks
+      .into_iter()
+      .skip(self.offset.unwrap_or(0) as usize)
+      .take(self.limit.map_or(usize::MAX, |x| x as usize))
+      .collect()
+  }
+}
+
+fn contains_text(task: &Task, text: &str) -> bool {
+  let text = text.to_lowercase();
+  task.title.to_lowercase().contains(&text)
+    || task
+      .body
+      .as_ref()
+      .is_some_and(|x| x.to_lowercase().contains(&text))
+}
+
+fn compare(a: &Task, b: &Task, key: TaskSortKey) -> Ordering {
+  match key {
+    TaskSortKey::Id => a.id.no.cmp(&b.id.no),
+    TaskSortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+    TaskSortKey::Status => status_order(&a.status).cmp(&status_order(&b.status)),
+    TaskSortKey::CreatedAt => a.created_at.cmp(&b.created_at),
+    TaskSortKey::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+  }
+}
+
+fn status_order(status: &TaskStatus) -> u8 {
+  match status {
+    TaskStatus::Backlog => 0,
+    TaskStatus::Queue => 1,
+    TaskStatus::InProgress => 2,
+    TaskStatus::Done => 3,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use crate::domain::task::{TaskId, TaskSchedule};
+
+  use super::*;
+
+  fn root() -> AggregateRoot<Task> {
+    let tasks = [
+      (1, "Write docs", TaskStatus::Done, 300),
+      (2, "deploy app", TaskStatus::InProgress, 100),
+      (3, "Fix bug", TaskStatus::InProgress, 200),
+      (4, "Deploy docs", TaskStatus::Backlog, 100),
+    ];
+    let states: HashMap<_, _> = tasks
+      .into_iter()
+      .map(|(no, title, status, updated_at)| {
+        let task = Task::builder()
+          .id(TaskId::new(no))
+          .title(title.to_string())
+          .status(status)
+          .created_at(no as Timestamp * 1000)
+          .updated_at(updated_at)
+          .schedule((no == 4).then(|| {
+            TaskSchedule::builder()
+              .at(0)
+              .status(TaskStatus::Queue)
+              .build()
+          }))
+          .build();
+        (task.id.to_string(), task)
+      })
+      .collect();
+    let versions = states.keys().map(|x| (x.to_owned(), 1)).collect();
+    AggregateRoot::new(states, versions)
+  }
+
+  fn ids(tasks: Vec<&Task>) -> Vec<i32> {
+    tasks.into_iter().map(|x| x.id.no).collect()
+  }
+
+  #[test]
+  fn order_by_id_by_default() {
+    let root = root();
+    assert_eq!(ids(TaskQuery::default().run(&root)), vec![1, 2, 3, 4]);
+  }
+
+  #[test]
+  fn filter_tasks() {
+    let root = root();
+    let query = TaskQuery {
+      status: Some(vec![TaskStatus::InProgress, TaskStatus::Backlog]),
+      text: Some("DEPLOY".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(ids(query.run(&root)), vec![2, 4]);
+
+    let query = TaskQuery {
+      created_from: Some(2000),
+      created_to: Some(4000),
+      has_schedule: Some(false),
+      ..Default::default()
+    };
+    assert_eq!(ids(query.run(&root)), vec![2, 3]);
+  }
+
+  #[test]
+  fn sort_stably_and_page() {
+    let root = root();
+    let query = TaskQuery {
+      sort: Some(TaskSortKey::UpdatedAt),
+      direction: Some(SortDirection::Desc),
+      ..Default::default()
+    };
+    // ties are ordered by id.
+    assert_eq!(ids(query.run(&root)), vec![1, 3, 2, 4]);
+
+    let query = TaskQuery {
+      sort: Some(TaskSortKey::Status),
+      offset: Some(1),
+      limit: Some(2),
+      ..Default::default()
+    };
+    assert_eq!(ids(query.run(&root)), vec![2, 3]);
+
+    let query = TaskQuery {
+      sort: Some(TaskSortKey::Title),
+      ..Default::default()
+    };
+    assert_eq!(ids(query.run(&root)), vec![2, 4, 3, 1]);
+  }
+
+  #[test]
+  fn deserialize_partial_query() {
+    let query: TaskQuery =
+      serde_json::from_str(r#"{"status":["queue"],"sort":"created_at","limit":10}"#).unwrap();
+    assert_eq!(query.status, Some(vec![TaskStatus::Queue]));
+    assert_eq!(query.sort, Some(TaskSortKey::CreatedAt));
+    assert_eq!(query.limit, Some(10));
+    assert_eq!(query.direction, None);
+  }
+}
@@ -0,0 +1,258 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, FixedOffset, TimeZone};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::eventsourcing::Timestamp;
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[typeshare]
+pub enum Weekday {
+  Monday,
+  Tuesday,
+  Wednesday,
+  Thursday,
+  Friday,
+  Saturday,
+  Sunday,
+}
+
+impl Weekday {
+  pub const WEEKDAYS: [Weekday; 5] = [
+    Self::Monday,
+    Self::Tuesday,
+    Self::Wednesday,
+    Self::Thursday,
+    Self::Friday,
+  ];
+}
+
+impl From<chrono::Weekday> for Weekday {
+  fn from(value: chrono::Weekday) -> Self {
+    match value {
+      chrono::Weekday::Mon => Self::Monday,
+      chrono::Weekday::Tue => Self::Tuesday,
+      chrono::Weekday::Wed => Self::Wednesday,
+      chrono::Weekday::Thu => Self::Thursday,
+      chrono::Weekday::Fri => Self::Friday,
+      chrono::Weekday::Sat => Self::Saturday,
+      chrono::Weekday::Sun => Self::Sunday,
+    }
+  }
+}
+
+impl Display for Weekday {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Self::Monday => "monday",
+        Self::Tuesday => "tuesday",
+        Self::Wednesday => "wednesday",
+        Self::Thursday => "thursday",
+        Self::Friday => "friday",
+        Self::Saturday => "saturday",
+        Self::Sunday => "sunday",
+      }
+    )
+  }
+}
+
+impl FromStr for Weekday {
+  type Err = crate::domain::Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "monday" | "mon" => Ok(Self::Monday),
+      "tuesday" | "tue" => Ok(Self::Tuesday),
+      "wednesday" | "wed" => Ok(Self::Wednesday),
+      "thursday" | "thu" => Ok(Self::Thursday),
+      "friday" | "fri" => Ok(Self::Friday),
+      "saturday" | "sat" => Ok(Self::Saturday),
+      "sunday" | "sun" => Ok(Self::Sunday),
+      _ => Err(Self::Err::InvalidWeekday),
+    }
+  }
+}
+
+/// Rule of recurring schedule.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "name", content = "data")]
+#[typeshare]
+pub enum Recurrence {
+  /// Every `every` milliseconds.
+  #[serde(rename = "interval", rename_all = "camelCase")]
+  Interval { every: Timestamp },
+  /// On `weekdays` (every day when empty) at `hour:minute` of local time.
+  #[serde(rename = "weekly", rename_all = "camelCase")]
+  Weekly {
+    weekdays: Vec<Weekday>,
+    hour: u32,
+    minute: u32,
+  },
+}
+
+impl Recurrence {
+  /// Next occurrence following the one at `at`, skipping occurrences not later than `now`.
+  pub fn next(&self, at: Timestamp, now: Timestamp, offset: &FixedOffset) -> Option<Timestamp> {
+    match self {
+      Self::Interval { every } => {
+        if *every <= 0 {
+          return None;
+        }
+        let skipped = (now - at).max(0) / every;
+        Some(at + (skipped + 1) * every)
+      }
+      Self::Weekly {
+        weekdays,
+        hour,
+        minute,
+      } => {
+        let after = at.max(now);
+        let date = offset.timestamp_millis_opt(after).single()?.date_naive();
+        (0..=7)
+          .filter_map(|x| date.checked_add_days(Days::new(x)))
+          .filter(|x| weekdays.is_empty() || weekdays.contains(&x.weekday().into()))
+          .filter_map(|x| x.and_hms_opt(*hour, *minute, 0))
+          .filter_map(|x| offset.from_local_datetime(&x).single())
+          .map(|x| x.timestamp_millis())
+          .find(|x| *x > after)
+      }
+    }
+  }
+}
+
+impl Display for Recurrence {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Interval { every } => {
+        let units = [
+          (7 * 24 * 60 * 60 * 1000, "w"),
+          (24 * 60 * 60 * 1000, "d"),
+          (60 * 60 * 1000, "h"),
+          (60 * 1000, "m"),
+          (1000, "s"),
+        ];
+        match units.iter().find(|(millis, _)| every % millis == 0) {
+          Some((millis, unit)) => write!(f, "every {}{}", every / millis, unit),
+          None => write!(f, "every {}", every),
+        }
+      }
+      Self::Weekly {
+        weekdays,
+        hour,
+        minute,
+      } => {
+        let days = match weekdays.as_slice() {
+          [] => "day".to_string(),
+          x if x == Weekday::WEEKDAYS => "weekday".to_string(),
+          x => x
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+        };
+        write!(f, "every {} at {:02}:{:02}", days, hour, minute)
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const HOUR: Timestamp = 60 * 60 * 1000;
+  const DAY: Timestamp = 24 * HOUR;
+
+  #[test]
+  fn next_interval_skips_missed_occurrences() {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    let rule = Recurrence::Interval { every: 2 * HOUR };
+    assert_eq!(rule.next(0, 0, &utc), Some(2 * HOUR));
+    assert_eq!(rule.next(0, 5 * HOUR, &utc), Some(6 * HOUR));
+    assert_eq!(rule.next(0, 6 * HOUR, &utc), Some(8 * HOUR));
+  }
+
+  #[test]
+  fn next_weekday_in_offset() {
+    let kst = FixedOffset::east_opt(9 * 60 * 60).unwrap();
+    let rule = Recurrence::Weekly {
+      weekdays: Weekday::WEEKDAYS.to_vec(),
+      hour: 9,
+      minute: 0,
+    };
+    // 1970-01-02 is friday, 09:00 KST is 00:00 UTC.
+    let friday = DAY;
+    assert_eq!(rule.next(friday - HOUR, 0, &kst), Some(friday));
+    // skips weekend to monday.
+    assert_eq!(rule.next(friday, friday, &kst), Some(friday + 3 * DAY));
+  }
+
+  #[test]
+  fn next_on_days() {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    let every_monday = Recurrence::Weekly {
+      weekdays: vec![Weekday::Monday],
+      hour: 0,
+      minute: 0,
+    };
+    // 1970-01-05 is monday.
+    assert_eq!(every_monday.next(0, 0, &utc), Some(4 * DAY));
+    assert_eq!(every_monday.next(4 * DAY, 0, &utc), Some(11 * DAY));
+
+    let every_day = Recurrence::Weekly {
+      weekdays: Vec::new(),
+      hour: 12,
+      minute: 30,
+    };
+    assert_eq!(
+      every_day.next(0, 13 * HOUR, &utc),
+      Some(DAY + 12 * HOUR + 30 * 60 * 1000)
+    );
+  }
+
+  #[test]
+  fn display() {
+    assert_eq!(
+      Recurrence::Interval { every: 2 * HOUR }.to_string(),
+      "every 2h"
+    );
+    assert_eq!(
+      Recurrence::Weekly {
+        weekdays: Weekday::WEEKDAYS.to_vec(),
+        hour: 9,
+        minute: 0,
+      }
+      .to_string(),
+      "every weekday at 09:00"
+    );
+    assert_eq!(
+      Recurrence::Weekly {
+        weekdays: vec![Weekday::Monday, Weekday::Friday],
+        hour: 18,
+        minute: 5,
+      }
+      .to_string(),
+      "every monday friday at 18:05"
+    );
+  }
+
+  #[test]
+  fn serialize() {
+    let rule = Recurrence::Weekly {
+      weekdays: vec![Weekday::Monday],
+      hour: 9,
+      minute: 0,
+    };
+    let json = serde_json::to_string(&rule).unwrap();
+    assert_eq!(
+      json,
+      r#"{"name":"weekly","data":{"weekdays":["monday"],"hour":9,"minute":0}}"#
+    );
+    assert_eq!(serde_json::from_str::<Recurrence>(&json).unwrap(), rule);
+  }
+}
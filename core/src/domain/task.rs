@@ -1,12 +1,15 @@
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-use chrono::Utc;
+use chrono::{FixedOffset, Utc};
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use typed_builder::TypedBuilder;
 use typeshare::typeshare;
+use uuid::Uuid;
 
-use crate::eventsourcing::{Aggregate, AggregateRoot, Command, Event, Timestamp};
+use crate::domain::recurrence::Recurrence;
+use crate::eventsourcing::{Aggregate, AggregateRoot, Command, Event, Timestamp, Version};
 
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
 #[non_exhaustive]
@@ -63,19 +66,51 @@ impl<'de> Deserialize<'de> for TaskId {
   }
 }
 
+/// Unique id of a task, which never changes unlike `TaskId`.
+/// Tasks created before uids were introduced are keyed by their number `#n` in its place.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+#[typeshare(serialized_as = "String")]
+pub struct TaskUid(String);
+
+impl TaskUid {
+  pub fn generate() -> Self {
+    Self(Uuid::new_v4().to_string())
+  }
+}
+
+impl Display for TaskUid {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl FromStr for TaskUid {
+  type Err = crate::domain::Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let uid = Uuid::parse_str(s).map_err(|_| Self::Err::InvalidTaskId)?;
+    Ok(Self(uid.to_string()))
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
 #[serde(rename_all = "camelCase")]
 #[typeshare]
 pub struct Task {
+  /// Aggregate id of the task when set, and `id` is only an alias which may be reassigned.
+  /// Tasks created before it was introduced don't have one, and keep `id` as aggregate id.
+  #[builder(default)]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub uid: Option<TaskUid>,
   pub id: TaskId,
   pub title: String,
   #[builder(default)]
   pub body: Option<String>,
   #[builder(default)]
   pub status: TaskStatus,
-  #[builder(default = Utc::now().timestamp())]
+  #[builder(default = Utc::now().timestamp_millis())]
   pub created_at: Timestamp,
-  #[builder(default = Utc::now().timestamp())]
+  #[builder(default = Utc::now().timestamp_millis())]
   pub updated_at: Timestamp,
   #[builder(default)]
   pub backlog_at: Option<Timestamp>,
@@ -87,15 +122,112 @@ pub struct Task {
   pub done_at: Option<Timestamp>,
   #[builder(default)]
   pub schedule: Option<TaskSchedule>,
+  #[builder(default)]
+  #[serde(default)]
+  pub tags: Vec<String>,
+  #[builder(default)]
+  #[serde(default)]
+  pub sessions: Vec<WorkSession>,
+  /// Tasks which must be done before this task starts, by aggregate id.
+  #[builder(default)]
+  #[serde(default)]
+  pub blocked_by: Vec<TaskUid>,
+  #[builder(default)]
+  #[serde(default)]
+  pub checklist: Vec<ChecklistItem>,
 }
 
 impl Task {
+  /// Aggregate id of the task, which other tasks refer to it by.
+  pub fn key(&self) -> TaskUid {
+    TaskUid(self.id())
+  }
+
   pub fn schedule_available(&self) -> bool {
     if let Some(schedule) = &self.schedule {
       return schedule.at <= Utc::now().timestamp_millis();
     }
     false
   }
+
+  pub fn has_tag(&self, tag: &str) -> bool {
+    self.tags.iter().any(|x| x == tag)
+  }
+
+  /// Checked items out of all checklist items, `None` without checklist.
+  pub fn checklist_progress(&self) -> Option<ChecklistProgress> {
+    if self.checklist.is_empty() {
+      return None;
+    }
+    Some(ChecklistProgress {
+      done: self.checklist.iter().filter(|x| x.checked).count() as u32,
+      total: self.checklist.len() as u32,
+    })
+  }
+
+  fn checklist_position(&self, item: u32) -> Result<usize, crate::domain::Error> {
+    self
+      .checklist
+      .iter()
+      .position(|x| x.id == item)
+      .ok_or(crate::domain::Error::ChecklistItemNotExists)
+  }
+
+  /// Total milliseconds spent in progress. An open session counts until `now`.
+  pub fn tracked_duration(&self, now: Timestamp) -> Timestamp {
+    self.sessions.iter().map(|x| x.duration(now)).sum()
+  }
+
+  fn start_session(&mut self, at: Timestamp) {
+    if self.sessions.last().is_some_and(|x| x.ended_at.is_none()) {
+      return;
+    }
+    self.sessions.push(WorkSession {
+      started_at: at,
+      ended_at: None,
+    });
+  }
+
+  fn end_session(&mut self, at: Timestamp) {
+    if let Some(session) = self.sessions.last_mut() {
+      if session.ended_at.is_none() {
+        session.ended_at = Some(at);
+      }
+    }
+  }
+}
+
+/// A period the task stayed in `TaskStatus::InProgress`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct WorkSession {
+  pub started_at: Timestamp,
+  pub ended_at: Option<Timestamp>,
+}
+
+impl WorkSession {
+  pub fn duration(&self, now: Timestamp) -> Timestamp {
+    (self.ended_at.unwrap_or(now) - self.started_at).max(0)
+  }
+}
+
+/// A step of the task, `id` is unique only in the task.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct ChecklistItem {
+  pub id: u32,
+  pub text: String,
+  pub checked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct ChecklistProgress {
+  pub done: u32,
+  pub total: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
@@ -104,12 +236,29 @@ impl Task {
 pub struct TaskSchedule {
   pub at: Timestamp,
   pub status: TaskStatus,
+  #[builder(default)]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub repeat: Option<Recurrence>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+impl TaskSchedule {
+  /// Next schedule of recurring one, which is later than `now`.
+  pub fn next(&self, now: Timestamp, offset: &FixedOffset) -> Option<TaskSchedule> {
+    let repeat = self.repeat.as_ref()?;
+    let at = repeat.next(self.at, now, offset)?;
+    Some(TaskSchedule {
+      at,
+      status: self.status.to_owned(),
+      repeat: Some(repeat.to_owned()),
+    })
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 #[typeshare]
 pub enum TaskStatus {
+  #[default]
   Backlog,
   Queue,
   InProgress,
@@ -131,9 +280,16 @@ impl Display for TaskStatus {
   }
 }
 
-impl Default for TaskStatus {
-  fn default() -> Self {
-    Self::Backlog
+impl FromStr for TaskStatus {
+  type Err = crate::domain::Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "backlog" => Ok(Self::Backlog),
+      "queue" => Ok(Self::Queue),
+      "in_progress" => Ok(Self::InProgress),
+      "done" => Ok(Self::Done),
+      _ => Err(Self::Err::InvalidTaskStatus),
+    }
   }
 }
 
@@ -143,7 +299,14 @@ impl Aggregate for Task {
   type Error = crate::domain::Error;
 
   fn id(&self) -> String {
-    self.id.to_string()
+    match &self.uid {
+      Some(uid) => uid.to_string(),
+      None => self.id.to_string(),
+    }
+  }
+
+  fn alias(&self) -> Option<String> {
+    Some(self.id.to_string())
   }
 
   fn handle_command(
@@ -152,14 +315,109 @@ impl Aggregate for Task {
     root: &AggregateRoot<Self>,
   ) -> Result<Self::Event, Self::Error> {
     match this {
-      Some(_) => match command {
+      Some(task) => match command {
         Self::Command::UpdateTitle { title, .. } => Ok(Self::Event::TitleUpdated { title }),
-        Self::Command::UpdateStatus { status, .. } => Ok(Self::Event::StatusUpdated { status }),
+        Self::Command::UpdateStatus { status, force, .. } => {
+          if status == TaskStatus::InProgress && !force {
+            if let Some(blocker) = unfinished_blockers(task, root).first() {
+              return Err(crate::domain::Error::BlockedBy(*blocker));
+            }
+          }
+          Ok(Self::Event::StatusUpdated { status })
+        }
         Self::Command::UpdateBody { body, .. } => Ok(Self::Event::BodyUpdated { body }),
         Self::Command::Delete { .. } => Ok(Self::Event::Deleted {}),
         Self::Command::UpdateSchedule { schedule, .. } => {
           Ok(Self::Event::ScheduleUpdated { schedule })
         }
+        Self::Command::AddTag { tag, .. } => {
+          let tag = normalize_tag(tag)?;
+          if task.has_tag(&tag) {
+            return Err(crate::domain::Error::TagAlreadyExists);
+          }
+          Ok(Self::Event::TagAdded { tag })
+        }
+        Self::Command::RemoveTag { tag, .. } => {
+          let tag = normalize_tag(tag)?;
+          if !task.has_tag(&tag) {
+            return Err(crate::domain::Error::TagNotExists);
+          }
+          Ok(Self::Event::TagRemoved { tag })
+        }
+        Self::Command::AddBlocker { blocker, .. } => {
+          let Some(blocker) = root.get_state(blocker.to_string()).map(Task::key) else {
+            return Err(crate::domain::Error::TaskNotExists);
+          };
+          if task.blocked_by.contains(&blocker) {
+            return Err(crate::domain::Error::BlockerAlreadyExists);
+          }
+          if depends_on(root, &blocker, &task.key()) {
+            return Err(crate::domain::Error::DependencyCycle);
+          }
+          Ok(Self::Event::BlockerAdded { blocker })
+        }
+        Self::Command::RemoveBlocker { blocker, .. } => {
+          let blocker = root
+            .get_state(blocker.to_string())
+            .map(Task::key)
+            .filter(|x| task.blocked_by.contains(x))
+            .ok_or(crate::domain::Error::BlockerNotExists)?;
+          Ok(Self::Event::BlockerRemoved { blocker })
+        }
+        Self::Command::AddChecklistItem {
+          text,
+          checked,
+          position,
+          ..
+        } => {
+          let text = text.trim();
+          if text.is_empty() {
+            return Err(crate::domain::Error::InvalidChecklistItem);
+          }
+          let id = task.checklist.iter().map(|x| x.id).max().unwrap_or(0) + 1;
+          let len = task.checklist.len() as u32;
+          Ok(Self::Event::ChecklistItemAdded {
+            item: ChecklistItem {
+              id,
+              text: text.to_string(),
+              checked,
+            },
+            position: position.unwrap_or(len).min(len),
+          })
+        }
+        Self::Command::ToggleChecklistItem { item, .. } => {
+          let position = task.checklist_position(item)?;
+          Ok(Self::Event::ChecklistItemToggled {
+            item,
+            checked: !task.checklist[position].checked,
+          })
+        }
+        Self::Command::MoveChecklistItem { item, position, .. } => {
+          task.checklist_position(item)?;
+          let last = task.checklist.len() as u32 - 1;
+          Ok(Self::Event::ChecklistItemMoved {
+            item,
+            position: position.min(last),
+          })
+        }
+        Self::Command::RemoveChecklistItem { item, .. } => {
+          task.checklist_position(item)?;
+          Ok(Self::Event::ChecklistItemRemoved { item })
+        }
+        Self::Command::FireSchedule { next, .. } => {
+          let schedule = task
+            .schedule
+            .as_ref()
+            .ok_or(crate::domain::Error::ScheduleNotExists)?;
+          if schedule.at > Utc::now().timestamp_millis() {
+            return Err(crate::domain::Error::ScheduleNotDue);
+          }
+          Ok(Self::Event::ScheduleFired {
+            status: schedule.status.to_owned(),
+            scheduled_at: schedule.at,
+            next,
+          })
+        }
         _ => Err(crate::domain::Error::TaskNotExists),
       },
       None => match command {
@@ -168,12 +426,14 @@ impl Aggregate for Task {
           status,
           schedule,
         } => Ok(Self::Event::Created {
-          id: get_next_task_id(&root),
+          uid: Some(TaskUid::generate()),
+          id: get_next_task_id(root),
           title,
           body: None,
           status: status.unwrap_or_default(),
           schedule,
         }),
+        Self::Command::Restore { task } => Ok(Self::Event::Restored { task: *task }),
         _ => Err(crate::domain::Error::TaskAlreadyExists),
       },
     }
@@ -182,36 +442,98 @@ impl Aggregate for Task {
   fn apply_event(
     this: Option<Self>,
     event: Self::Event,
+    occurred_at: Timestamp,
   ) -> Result<(String, Option<Self>), Self::Error> {
-    let now = Utc::now().timestamp_millis();
     match this {
       Some(mut task) => match event {
         Self::Event::TitleUpdated { title } => {
           task.title = title;
-          task.updated_at = now;
-          Ok((task.id.to_string(), Some(task)))
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
         }
         Self::Event::StatusUpdated { status } => {
-          task.status = status.to_owned();
-          task.updated_at = now;
-          update_task_status_timestamp(&mut task, now);
-          Ok((task.id.to_string(), Some(task)))
+          update_task_status(&mut task, status, occurred_at);
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::BlockerAdded { blocker } => {
+          if !task.blocked_by.contains(&blocker) {
+            task.blocked_by.push(blocker);
+          }
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::BlockerRemoved { blocker } => {
+          task.blocked_by.retain(|x| x != &blocker);
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::ChecklistItemAdded { item, position } => {
+          let position = (position as usize).min(task.checklist.len());
+          task.checklist.insert(position, item);
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::ChecklistItemToggled { item, checked } => {
+          if let Some(x) = task.checklist.iter_mut().find(|x| x.id == item) {
+            x.checked = checked;
+          }
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::ChecklistItemMoved { item, position } => {
+          if let Ok(from) = task.checklist_position(item) {
+            let moved = task.checklist.remove(from);
+            let position = (position as usize).min(task.checklist.len());
+            task.checklist.insert(position, moved);
+          }
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::ChecklistItemRemoved { item } => {
+          task.checklist.retain(|x| x.id != item);
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::ScheduleFired {
+          status,
+          scheduled_at,
+          next,
+          ..
+        } => {
+          update_task_status(&mut task, status, scheduled_at);
+          task.schedule = next;
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
         }
         Self::Event::BodyUpdated { body } => {
           task.body = body;
-          task.updated_at = now;
-          Ok((task.id.to_string(), Some(task)))
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
         }
-        Self::Event::Deleted { .. } => Ok((task.id.to_string(), None)),
+        Self::Event::Deleted { .. } => Ok((task.id(), None)),
         Self::Event::ScheduleUpdated { schedule, .. } => {
           task.schedule = schedule;
-          task.updated_at = now;
-          Ok((task.id.to_string(), Some(task)))
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::TagAdded { tag } => {
+          if !task.has_tag(&tag) {
+            task.tags.push(tag);
+          }
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::TagRemoved { tag } => {
+          task.tags.retain(|x| x != &tag);
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
         }
         _ => Err(crate::domain::Error::TaskAlreadyExists),
       },
       None => match event {
         Self::Event::Created {
+          uid,
           id,
           title,
           body,
@@ -219,14 +541,24 @@ impl Aggregate for Task {
           schedule,
         } => {
           let mut task = Task::builder()
+            .uid(uid)
             .id(id)
             .title(title)
             .body(body)
             .status(status)
             .schedule(schedule)
+            .created_at(occurred_at)
+            .updated_at(occurred_at)
             .build();
-          update_task_status_timestamp(&mut task, now);
-          Ok((task.id.to_string(), Some(task)))
+          if task.status == TaskStatus::InProgress {
+            task.start_session(occurred_at);
+          }
+          update_task_status_timestamp(&mut task, occurred_at);
+          Ok((task.id(), Some(task)))
+        }
+        Self::Event::Restored { mut task } => {
+          task.updated_at = occurred_at;
+          Ok((task.id(), Some(task)))
         }
         _ => Err(crate::domain::Error::TaskNotExists),
       },
@@ -234,6 +566,17 @@ impl Aggregate for Task {
   }
 }
 
+fn update_task_status(task: &mut Task, status: TaskStatus, at: Timestamp) {
+  match (&task.status, &status) {
+    (TaskStatus::InProgress, TaskStatus::InProgress) => {}
+    (_, TaskStatus::InProgress) => task.start_session(at),
+    (TaskStatus::InProgress, _) => task.end_session(at),
+    _ => {}
+  }
+  task.status = status;
+  update_task_status_timestamp(task, at);
+}
+
 fn update_task_status_timestamp(task: &mut Task, at: Timestamp) {
   match task.status {
     TaskStatus::Backlog => {
@@ -251,16 +594,151 @@ fn update_task_status_timestamp(task: &mut Task, at: Timestamp) {
   };
 }
 
-fn get_next_task_id(root: &AggregateRoot<Task>) -> TaskId {
-  let mut no_list = root
-    .ids()
+/// Returns command which reverts `event`, from the task state right before the event.
+/// Tasks the event refers to are looked up in `root` by their current number.
+pub fn compensating_command(
+  before: Option<&Task>,
+  event: &TaskEvent,
+  root: &AggregateRoot<Task>,
+) -> Option<TaskCommand> {
+  match (before, event) {
+    (_, TaskEvent::Created { id, .. }) => Some(TaskCommand::Delete { id: *id }),
+    (_, TaskEvent::Restored { task }) => Some(TaskCommand::Delete { id: task.id }),
+    (Some(before), TaskEvent::TitleUpdated { .. }) => Some(TaskCommand::UpdateTitle {
+      id: before.id,
+      title: before.title.to_owned(),
+    }),
+    (Some(before), TaskEvent::StatusUpdated { .. }) => Some(TaskCommand::UpdateStatus {
+      id: before.id,
+      status: before.status.to_owned(),
+      force: true,
+    }),
+    (Some(before), TaskEvent::BodyUpdated { .. }) => Some(TaskCommand::UpdateBody {
+      id: before.id,
+      body: before.body.to_owned(),
+    }),
+    (Some(before), TaskEvent::ScheduleUpdated { .. }) => Some(TaskCommand::UpdateSchedule {
+      id: before.id,
+      schedule: before.schedule.to_owned(),
+    }),
+    (Some(before), TaskEvent::TagAdded { tag }) => Some(TaskCommand::RemoveTag {
+      id: before.id,
+      tag: tag.to_owned(),
+    }),
+    (Some(before), TaskEvent::TagRemoved { tag }) => Some(TaskCommand::AddTag {
+      id: before.id,
+      tag: tag.to_owned(),
+    }),
+    (Some(before), TaskEvent::Deleted {}) => Some(TaskCommand::Restore {
+      task: Box::new(before.to_owned()),
+    }),
+    (Some(before), TaskEvent::BlockerAdded { blocker }) => Some(TaskCommand::RemoveBlocker {
+      id: before.id,
+      blocker: root.get_state(blocker.to_string())?.id,
+    }),
+    (Some(before), TaskEvent::BlockerRemoved { blocker }) => Some(TaskCommand::AddBlocker {
+      id: before.id,
+      blocker: root.get_state(blocker.to_string())?.id,
+    }),
+    (Some(before), TaskEvent::ChecklistItemAdded { item, .. }) => {
+      Some(TaskCommand::RemoveChecklistItem {
+        id: before.id,
+        item: item.id,
+      })
+    }
+    (Some(before), TaskEvent::ChecklistItemToggled { item, .. }) => {
+      Some(TaskCommand::ToggleChecklistItem {
+        id: before.id,
+        item: *item,
+      })
+    }
+    (Some(before), TaskEvent::ChecklistItemMoved { item, .. }) => {
+      let position = before.checklist_position(*item).ok()?;
+      Some(TaskCommand::MoveChecklistItem {
+        id: before.id,
+        item: *item,
+        position: position as u32,
+      })
+    }
+    (Some(before), TaskEvent::ChecklistItemRemoved { item }) => {
+      let position = before.checklist_position(*item).ok()?;
+      let removed = &before.checklist[position];
+      Some(TaskCommand::AddChecklistItem {
+        id: before.id,
+        text: removed.text.to_owned(),
+        checked: removed.checked,
+        position: Some(position as u32),
+      })
+    }
+    // fired by scheduler, not by user.
+    (Some(_), TaskEvent::ScheduleFired { .. }) => None,
+    (None, _) => None,
+  }
+}
+
+fn normalize_tag(tag: String) -> Result<String, crate::domain::Error> {
+  let tag = tag.trim();
+  if tag.is_empty() || tag.contains(char::is_whitespace) {
+    return Err(crate::domain::Error::InvalidTag);
+  }
+  Ok(tag.to_string())
+}
+
+/// Numbers of the blockers of the task. Deleted blockers are ignored.
+pub fn blockers(task: &Task, root: &AggregateRoot<Task>) -> Vec<TaskId> {
+  task
+    .blocked_by
     .iter()
-    .map(|x| x.to_string())
-    .filter_map(|x| TaskId::try_from(x).ok())
-    .map(|x| x.no)
-    .collect::<Vec<_>>();
-  no_list.sort();
-  let last_no = no_list.last().cloned().unwrap_or(0);
+    .filter_map(|x| root.states.get(&x.0))
+    .map(|x| x.id)
+    .collect()
+}
+
+/// Blockers of the task which are not done yet. Deleted blockers are ignored.
+pub fn unfinished_blockers(task: &Task, root: &AggregateRoot<Task>) -> Vec<TaskId> {
+  task
+    .blocked_by
+    .iter()
+    .filter_map(|x| root.states.get(&x.0))
+    .filter(|x| x.status != TaskStatus::Done)
+    .map(|x| x.id)
+    .collect()
+}
+
+/// Tasks blocked by the task, the other side of `Task::blocked_by`.
+pub fn blocking_tasks(task: &Task, root: &AggregateRoot<Task>) -> Vec<TaskId> {
+  let key = task.key();
+  let mut blocking: Vec<_> = root
+    .states
+    .values()
+    .filter(|x| x.blocked_by.contains(&key))
+    .map(|x| x.id)
+    .collect();
+  blocking.sort_by_key(|x| x.no);
+  blocking
+}
+
+/// Whether `from` is blocked by `to`, directly or through other blockers.
+fn depends_on(root: &AggregateRoot<Task>, from: &TaskUid, to: &TaskUid) -> bool {
+  let mut stack = vec![from];
+  let mut visited = Vec::new();
+  while let Some(key) = stack.pop() {
+    if key == to {
+      return true;
+    }
+    if visited.contains(&key) {
+      continue;
+    }
+    visited.push(key);
+    if let Some(task) = root.states.get(&key.0) {
+      stack.extend(task.blocked_by.iter());
+    }
+  }
+  false
+}
+
+pub fn get_next_task_id(root: &AggregateRoot<Task>) -> TaskId {
+  let last_no = root.states.values().map(|x| x.id.no).max().unwrap_or(0);
   TaskId::new(last_no + 1)
 }
 
@@ -270,6 +748,9 @@ fn get_next_task_id(root: &AggregateRoot<Task>) -> TaskId {
 pub enum TaskEvent {
   #[serde(rename = "task.created", rename_all = "camelCase")]
   Created {
+    /// `None` in events written before tasks had unique ids.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uid: Option<TaskUid>,
     id: TaskId,
     title: String,
     body: Option<String>,
@@ -286,6 +767,77 @@ pub enum TaskEvent {
   Deleted {},
   #[serde(rename = "task.scheduleUpdated", rename_all = "camelCase")]
   ScheduleUpdated { schedule: Option<TaskSchedule> },
+  #[serde(rename = "task.tagAdded", rename_all = "camelCase")]
+  TagAdded { tag: String },
+  #[serde(rename = "task.tagRemoved", rename_all = "camelCase")]
+  TagRemoved { tag: String },
+  #[serde(rename = "task.blockerAdded", rename_all = "camelCase")]
+  BlockerAdded { blocker: TaskUid },
+  #[serde(rename = "task.blockerRemoved", rename_all = "camelCase")]
+  BlockerRemoved { blocker: TaskUid },
+  #[serde(rename = "task.checklistItemAdded", rename_all = "camelCase")]
+  ChecklistItemAdded { item: ChecklistItem, position: u32 },
+  #[serde(rename = "task.checklistItemToggled", rename_all = "camelCase")]
+  ChecklistItemToggled { item: u32, checked: bool },
+  #[serde(rename = "task.checklistItemMoved", rename_all = "camelCase")]
+  ChecklistItemMoved { item: u32, position: u32 },
+  #[serde(rename = "task.checklistItemRemoved", rename_all = "camelCase")]
+  ChecklistItemRemoved { item: u32 },
+  #[serde(rename = "task.restored", rename_all = "camelCase")]
+  Restored { task: Task },
+  /// Status changed by schedule, `scheduled_at` is kept even if it fired late (e.g. after downtime).
+  /// When it fired is the occurrence time of the event.
+  #[serde(rename = "task.scheduleFired", rename_all = "camelCase")]
+  ScheduleFired {
+    status: TaskStatus,
+    scheduled_at: Timestamp,
+    next: Option<TaskSchedule>,
+  },
+}
+
+impl TaskEvent {
+  /// Replaces numbers of tasks the event refers to, e.g. to resolve id collisions on sync.
+  pub fn map_task_ids(self, f: impl Fn(TaskId) -> TaskId) -> Self {
+    match self {
+      Self::Created {
+        uid,
+        id,
+        title,
+        body,
+        status,
+        schedule,
+      } => Self::Created {
+        uid,
+        id: f(id),
+        title,
+        body,
+        status,
+        schedule,
+      },
+      Self::Restored { mut task } => {
+        task.id = f(task.id);
+        Self::Restored { task }
+      }
+      _ => self,
+    }
+  }
+
+  /// Replaces aggregate ids of blockers the event refers to, e.g. of tasks given a uid on sync.
+  pub fn map_task_keys(self, f: impl Fn(TaskUid) -> TaskUid) -> Self {
+    match self {
+      Self::BlockerAdded { blocker } => Self::BlockerAdded {
+        blocker: f(blocker),
+      },
+      Self::BlockerRemoved { blocker } => Self::BlockerRemoved {
+        blocker: f(blocker),
+      },
+      Self::Restored { mut task } => {
+        task.blocked_by = task.blocked_by.into_iter().map(f).collect();
+        Self::Restored { task }
+      }
+      _ => self,
+    }
+  }
 }
 
 impl Event for TaskEvent {
@@ -297,6 +849,16 @@ impl Event for TaskEvent {
       Self::BodyUpdated { .. } => "task.bodyUpdated",
       Self::Deleted { .. } => "task.deleted",
       Self::ScheduleUpdated { .. } => "task.scheduleUpdated",
+      Self::TagAdded { .. } => "task.tagAdded",
+      Self::TagRemoved { .. } => "task.tagRemoved",
+      Self::BlockerAdded { .. } => "task.blockerAdded",
+      Self::BlockerRemoved { .. } => "task.blockerRemoved",
+      Self::ChecklistItemAdded { .. } => "task.checklistItemAdded",
+      Self::ChecklistItemToggled { .. } => "task.checklistItemToggled",
+      Self::ChecklistItemMoved { .. } => "task.checklistItemMoved",
+      Self::ChecklistItemRemoved { .. } => "task.checklistItemRemoved",
+      Self::Restored { .. } => "task.restored",
+      Self::ScheduleFired { .. } => "task.scheduleFired",
     }
   }
 }
@@ -313,8 +875,14 @@ pub enum TaskCommand {
   },
   #[serde(rename = "task.updateTitle", rename_all = "camelCase")]
   UpdateTitle { id: TaskId, title: String },
+  /// Moving to in progress fails while blockers are not done, unless `force` is set.
   #[serde(rename = "task.updateStatus", rename_all = "camelCase")]
-  UpdateStatus { id: TaskId, status: TaskStatus },
+  UpdateStatus {
+    id: TaskId,
+    status: TaskStatus,
+    #[serde(default)]
+    force: bool,
+  },
   #[serde(rename = "task.updateBody", rename_all = "camelCase")]
   UpdateBody { id: TaskId, body: Option<String> },
   #[serde(rename = "task.delete", rename_all = "camelCase")]
@@ -324,6 +892,45 @@ pub enum TaskCommand {
     id: TaskId,
     schedule: Option<TaskSchedule>,
   },
+  #[serde(rename = "task.addTag", rename_all = "camelCase")]
+  AddTag { id: TaskId, tag: String },
+  #[serde(rename = "task.removeTag", rename_all = "camelCase")]
+  RemoveTag { id: TaskId, tag: String },
+  #[serde(rename = "task.addBlocker", rename_all = "camelCase")]
+  AddBlocker { id: TaskId, blocker: TaskId },
+  #[serde(rename = "task.removeBlocker", rename_all = "camelCase")]
+  RemoveBlocker { id: TaskId, blocker: TaskId },
+  /// Appends the item unless `position` is given.
+  #[serde(rename = "task.addChecklistItem", rename_all = "camelCase")]
+  AddChecklistItem {
+    id: TaskId,
+    text: String,
+    #[serde(default)]
+    checked: bool,
+    #[serde(default)]
+    position: Option<u32>,
+  },
+  #[serde(rename = "task.toggleChecklistItem", rename_all = "camelCase")]
+  ToggleChecklistItem { id: TaskId, item: u32 },
+  #[serde(rename = "task.moveChecklistItem", rename_all = "camelCase")]
+  MoveChecklistItem {
+    id: TaskId,
+    item: u32,
+    position: u32,
+  },
+  #[serde(rename = "task.removeChecklistItem", rename_all = "camelCase")]
+  RemoveChecklistItem { id: TaskId, item: u32 },
+  #[serde(rename = "task.restore", rename_all = "camelCase")]
+  Restore { task: Box<Task> },
+  /// Applies due schedule, replacing it with `next` one.
+  /// Skipped with `expected_version` when the task changed after the schedule was read.
+  #[serde(rename = "task.fireSchedule", rename_all = "camelCase")]
+  FireSchedule {
+    id: TaskId,
+    next: Option<TaskSchedule>,
+    #[serde(default)]
+    expected_version: Option<Version>,
+  },
 }
 
 impl Command for TaskCommand {
@@ -335,6 +942,16 @@ impl Command for TaskCommand {
       Self::UpdateBody { .. } => "task.updateBody",
       Self::Delete { .. } => "task.delete",
       Self::UpdateSchedule { .. } => "task.updateSchedule",
+      Self::AddTag { .. } => "task.addTag",
+      Self::RemoveTag { .. } => "task.removeTag",
+      Self::AddBlocker { .. } => "task.addBlocker",
+      Self::RemoveBlocker { .. } => "task.removeBlocker",
+      Self::AddChecklistItem { .. } => "task.addChecklistItem",
+      Self::ToggleChecklistItem { .. } => "task.toggleChecklistItem",
+      Self::MoveChecklistItem { .. } => "task.moveChecklistItem",
+      Self::RemoveChecklistItem { .. } => "task.removeChecklistItem",
+      Self::Restore { .. } => "task.restore",
+      Self::FireSchedule { .. } => "task.fireSchedule",
     }
   }
 
@@ -346,7 +963,556 @@ impl Command for TaskCommand {
       Self::UpdateBody { id, .. } => Some(id),
       Self::Delete { id } => Some(id),
       Self::UpdateSchedule { id, .. } => Some(id),
+      Self::AddTag { id, .. } => Some(id),
+      Self::RemoveTag { id, .. } => Some(id),
+      Self::AddBlocker { id, .. } => Some(id),
+      Self::RemoveBlocker { id, .. } => Some(id),
+      Self::AddChecklistItem { id, .. } => Some(id),
+      Self::ToggleChecklistItem { id, .. } => Some(id),
+      Self::MoveChecklistItem { id, .. } => Some(id),
+      Self::RemoveChecklistItem { id, .. } => Some(id),
+      Self::Restore { task } => Some(&task.id),
+      Self::FireSchedule { id, .. } => Some(id),
     }
     .map(|x| x.to_string())
   }
+
+  fn expected_version(&self) -> Option<Version> {
+    match self {
+      Self::FireSchedule {
+        expected_version, ..
+      } => *expected_version,
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::eventsourcing::StoredEvent;
+
+  use super::*;
+
+  fn create_task(root: &mut AggregateRoot<Task>) -> TaskId {
+    root
+      .execute_command(TaskCommand::Create {
+        title: "Write docs".to_string(),
+        status: None,
+        schedule: None,
+      })
+      .unwrap();
+    TaskId::new(1)
+  }
+
+  #[test]
+  fn add_and_remove_tags() {
+    let mut root = AggregateRoot::<Task>::default();
+    let id = create_task(&mut root);
+    root
+      .execute_command(TaskCommand::AddTag {
+        id,
+        tag: " work ".to_string(),
+      })
+      .unwrap();
+    root
+      .execute_command(TaskCommand::AddTag {
+        id,
+        tag: "docs".to_string(),
+      })
+      .unwrap();
+    assert_eq!(root.get_state("#1").unwrap().tags, vec!["work", "docs"]);
+
+    root
+      .execute_command(TaskCommand::RemoveTag {
+        id,
+        tag: "work".to_string(),
+      })
+      .unwrap();
+    assert_eq!(root.get_state("#1").unwrap().tags, vec!["docs"]);
+  }
+
+  #[test]
+  fn error_on_invalid_tag_commands() {
+    let mut root = AggregateRoot::<Task>::default();
+    let id = create_task(&mut root);
+    let add = |tag: &str| TaskCommand::AddTag {
+      id,
+      tag: tag.to_string(),
+    };
+    root.execute_command(add("work")).unwrap();
+
+    assert!(matches!(
+      root.execute_command(add("work")),
+      Err(crate::domain::Error::TagAlreadyExists)
+    ));
+    assert!(matches!(
+      root.execute_command(add("two words")),
+      Err(crate::domain::Error::InvalidTag)
+    ));
+    assert!(matches!(
+      root.execute_command(TaskCommand::RemoveTag {
+        id,
+        tag: "home".to_string(),
+      }),
+      Err(crate::domain::Error::TagNotExists)
+    ));
+  }
+
+  #[test]
+  fn replay_events_without_tags() {
+    let raw = r##"{"aggregateId":"#1","version":1,"event":{"name":"task.created","data":{"id":"#1","title":"Old","body":null,"status":"backlog","schedule":null}}}"##;
+    let stored: StoredEvent<serde_json::Value> = serde_json::from_str(raw).unwrap();
+    let persisted = stored.upcast(0).unwrap();
+    let mut root = AggregateRoot::<Task>::default();
+    root.save_events(vec![persisted]).unwrap();
+    assert!(root.get_state("#1").unwrap().tags.is_empty());
+
+    let raw = r##"{"id":"#1","title":"Old","body":null,"status":"backlog","createdAt":0,"updatedAt":0,"backlogAt":null,"inProgressAt":null,"queueAt":null,"doneAt":null,"schedule":null}"##;
+    let task: Task = serde_json::from_str(raw).unwrap();
+    assert!(task.tags.is_empty());
+  }
+
+  #[test]
+  fn key_events_by_uid() {
+    let mut root = AggregateRoot::<Task>::default();
+    let id = create_task(&mut root);
+    let uid = root.get_state("#1").unwrap().uid.clone().unwrap();
+    let persisted = root
+      .execute_command(TaskCommand::UpdateTitle {
+        id,
+        title: "Write more docs".to_string(),
+      })
+      .unwrap();
+    assert_eq!(persisted.aggregate_id, uid.to_string());
+    assert_eq!(root.get_version(uid.to_string()), Some(&2));
+    assert_eq!(root.get_version("#1"), Some(&2));
+
+    // number of the deleted task is given to the next one, with another uid.
+    root.execute_command(TaskCommand::Delete { id }).unwrap();
+    create_task(&mut root);
+    assert_ne!(root.get_state("#1").unwrap().uid, Some(uid.to_owned()));
+    assert_eq!(root.get_version("#1"), Some(&1));
+    assert_eq!(root.get_version(uid.to_string()), Some(&3));
+  }
+
+  #[test]
+  fn key_legacy_events_by_number() {
+    let raw = r##"[
+      {"aggregateId":"#1","version":1,"event":{"name":"task.created","data":{"id":"#1","title":"Old","body":null,"status":"backlog","schedule":null}}},
+      {"aggregateId":"#1","version":2,"event":{"name":"task.titleUpdated","data":{"title":"Renamed"}}}
+    ]"##;
+    let stored: Vec<StoredEvent<serde_json::Value>> = serde_json::from_str(raw).unwrap();
+    let events = stored.into_iter().map(|x| x.upcast(0).unwrap()).collect();
+    let mut root = AggregateRoot::<Task>::default();
+    root.save_events(events).unwrap();
+    assert_eq!(root.ids(), vec!["#1"]);
+    assert_eq!(root.get_state("#1").unwrap().uid, None);
+
+    let persisted = root
+      .execute_command(TaskCommand::Delete { id: TaskId::new(1) })
+      .unwrap();
+    assert_eq!(persisted.aggregate_id, "#1");
+    assert_eq!(persisted.version, 3);
+  }
+
+  #[test]
+  fn compensate_events() {
+    let mut root = AggregateRoot::<Task>::default();
+    let id = create_task(&mut root);
+    let before = root.get_state("#1").cloned().unwrap();
+    let persisted = root
+      .execute_command(TaskCommand::UpdateStatus {
+        id,
+        status: TaskStatus::Done,
+        force: false,
+      })
+      .unwrap();
+    let command = compensating_command(Some(&before), &persisted.event, &root).unwrap();
+    assert_eq!(
+      command,
+      TaskCommand::UpdateStatus {
+        id,
+        status: TaskStatus::Backlog,
+        force: true,
+      }
+    );
+
+    let persisted = root.execute_command(TaskCommand::Delete { id }).unwrap();
+    assert!(root.get_state("#1").is_none());
+    let command = compensating_command(Some(&before), &persisted.event, &root).unwrap();
+    root.execute_command(command).unwrap();
+    assert_eq!(root.get_state("#1").unwrap().title, before.title);
+    assert_eq!(root.get_version("#1"), Some(&4));
+  }
+
+  #[test]
+  fn compensate_created_event_with_delete() {
+    let mut root = AggregateRoot::<Task>::default();
+    let persisted = root
+      .execute_command(TaskCommand::Create {
+        title: "Write docs".to_string(),
+        status: None,
+        schedule: None,
+      })
+      .unwrap();
+    assert_eq!(
+      compensating_command(None, &persisted.event, &root),
+      Some(TaskCommand::Delete { id: TaskId::new(1) })
+    );
+    assert_eq!(
+      compensating_command(
+        None,
+        &TaskEvent::TitleUpdated {
+          title: "x".to_string()
+        },
+        &root
+      ),
+      None
+    );
+  }
+
+  #[test]
+  fn track_work_sessions_from_status_updates() {
+    let mut task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Deploy".into())
+      .build();
+    let transitions = [
+      (TaskStatus::InProgress, 1_000),
+      (TaskStatus::InProgress, 2_000),
+      (TaskStatus::Queue, 5_000),
+      (TaskStatus::InProgress, 10_000),
+      (TaskStatus::Done, 12_000),
+    ];
+    for (status, at) in transitions {
+      let event = TaskEvent::StatusUpdated { status };
+      task = Task::apply_event(Some(task), event, at).unwrap().1.unwrap();
+    }
+    assert_eq!(
+      task.sessions,
+      vec![
+        WorkSession {
+          started_at: 1_000,
+          ended_at: Some(5_000),
+        },
+        WorkSession {
+          started_at: 10_000,
+          ended_at: Some(12_000),
+        },
+      ]
+    );
+    assert_eq!(task.tracked_duration(20_000), 6_000);
+    assert_eq!(task.done_at, Some(12_000));
+  }
+
+  #[test]
+  fn use_occurrence_time_for_old_events() {
+    let json = r##"{"name":"task.statusUpdated","data":{"status":"in_progress"}}"##;
+    let event: TaskEvent = serde_json::from_str(json).unwrap();
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Deploy".into())
+      .build();
+    let task = Task::apply_event(Some(task), event, 3_000)
+      .unwrap()
+      .1
+      .unwrap();
+    assert_eq!(task.in_progress_at, Some(3_000));
+    assert_eq!(task.updated_at, 3_000);
+    assert_eq!(task.tracked_duration(4_000), 1_000);
+  }
+
+  #[test]
+  fn replay_to_same_state() {
+    let mut root = AggregateRoot::<Task>::default();
+    let id = TaskId::new(1);
+    let commands = [
+      TaskCommand::Create {
+        title: "Deploy".to_string(),
+        status: None,
+        schedule: None,
+      },
+      TaskCommand::UpdateTitle {
+        id,
+        title: "Deploy app".to_string(),
+      },
+      TaskCommand::AddTag {
+        id,
+        tag: "work".to_string(),
+      },
+    ];
+    let mut events = vec![];
+    for command in commands {
+      events.push(root.execute_command(command).unwrap());
+    }
+    let original = root.get_state(id.to_string()).unwrap();
+    assert_eq!(original.updated_at, events[2].occurred_at);
+
+    let mut replayed = AggregateRoot::<Task>::default();
+    replayed.save_events(events).unwrap();
+    assert_eq!(replayed.get_state(id.to_string()), Some(original));
+  }
+
+  #[test]
+  fn fire_schedule_with_original_time() {
+    let mut root = AggregateRoot::<Task>::default();
+    let id = create_task(&mut root);
+    let next = TaskSchedule::builder()
+      .at(Utc::now().timestamp_millis() + 60_000)
+      .status(TaskStatus::InProgress)
+      .build();
+    root
+      .execute_command(TaskCommand::UpdateSchedule {
+        id,
+        schedule: Some(
+          TaskSchedule::builder()
+            .at(1_000)
+            .status(TaskStatus::InProgress)
+            .build(),
+        ),
+      })
+      .unwrap();
+
+    let persisted = root
+      .execute_command(TaskCommand::FireSchedule {
+        id,
+        next: Some(next.clone()),
+        expected_version: None,
+      })
+      .unwrap();
+
+    assert!(matches!(
+      persisted.event,
+      TaskEvent::ScheduleFired {
+        scheduled_at: 1_000,
+        ..
+      }
+    ));
+    let task = root.get_state("#1").unwrap();
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.in_progress_at, Some(1_000));
+    assert_eq!(task.sessions[0].started_at, 1_000);
+    assert_eq!(task.schedule, Some(next));
+
+    assert!(matches!(
+      root.execute_command(TaskCommand::FireSchedule {
+        id,
+        next: None,
+        expected_version: None,
+      }),
+      Err(crate::domain::Error::ScheduleNotDue)
+    ));
+  }
+
+  #[test]
+  fn block_tasks_without_cycle() {
+    let mut root = AggregateRoot::<Task>::default();
+    for title in ["Design", "Build", "Release"] {
+      root
+        .execute_command(TaskCommand::Create {
+          title: title.to_string(),
+          status: None,
+          schedule: None,
+        })
+        .unwrap();
+    }
+    let (design, build, release) = (TaskId::new(1), TaskId::new(2), TaskId::new(3));
+    for (id, blocker) in [(build, design), (release, build)] {
+      root
+        .execute_command(TaskCommand::AddBlocker { id, blocker })
+        .unwrap();
+    }
+    let task = |id: TaskId| root.get_state(id.to_string()).unwrap();
+    assert_eq!(task(release).blocked_by, vec![task(build).key()]);
+    assert_eq!(blockers(task(release), &root), vec![build]);
+    assert_eq!(blocking_tasks(task(design), &root), vec![build]);
+
+    for (id, blocker) in [(design, release), (design, design)] {
+      assert!(matches!(
+        root.execute_command(TaskCommand::AddBlocker { id, blocker }),
+        Err(crate::domain::Error::DependencyCycle)
+      ));
+    }
+    assert!(matches!(
+      root.execute_command(TaskCommand::AddBlocker {
+        id: release,
+        blocker: build,
+      }),
+      Err(crate::domain::Error::BlockerAlreadyExists)
+    ));
+
+    root
+      .execute_command(TaskCommand::RemoveBlocker {
+        id: release,
+        blocker: build,
+      })
+      .unwrap();
+    root
+      .execute_command(TaskCommand::AddBlocker {
+        id: design,
+        blocker: release,
+      })
+      .unwrap();
+  }
+
+  #[test]
+  fn keep_blocker_when_number_is_reused() {
+    let mut root = AggregateRoot::<Task>::default();
+    for title in ["Build", "Design"] {
+      root
+        .execute_command(TaskCommand::Create {
+          title: title.to_string(),
+          status: None,
+          schedule: None,
+        })
+        .unwrap();
+    }
+    let (build, design) = (TaskId::new(1), TaskId::new(2));
+    let persisted = root
+      .execute_command(TaskCommand::AddBlocker {
+        id: build,
+        blocker: design,
+      })
+      .unwrap();
+    let before = root.get_state("#1").cloned().unwrap();
+    root
+      .execute_command(TaskCommand::Delete { id: design })
+      .unwrap();
+    assert_eq!(
+      compensating_command(Some(&before), &persisted.event, &root),
+      None
+    );
+
+    // the new task takes number of the deleted blocker.
+    root
+      .execute_command(TaskCommand::Create {
+        title: "Redesign".to_string(),
+        status: None,
+        schedule: None,
+      })
+      .unwrap();
+    let task = root.get_state("#1").unwrap();
+    assert!(blockers(task, &root).is_empty());
+    assert!(blocking_tasks(root.get_state("#2").unwrap(), &root).is_empty());
+    assert!(matches!(
+      root.execute_command(TaskCommand::RemoveBlocker {
+        id: build,
+        blocker: design,
+      }),
+      Err(crate::domain::Error::BlockerNotExists)
+    ));
+    root
+      .execute_command(TaskCommand::UpdateStatus {
+        id: build,
+        status: TaskStatus::InProgress,
+        force: false,
+      })
+      .unwrap();
+  }
+
+  #[test]
+  fn start_blocked_task_only_when_forced() {
+    let mut root = AggregateRoot::<Task>::default();
+    for title in ["Design", "Build"] {
+      root
+        .execute_command(TaskCommand::Create {
+          title: title.to_string(),
+          status: None,
+          schedule: None,
+        })
+        .unwrap();
+    }
+    let (design, build) = (TaskId::new(1), TaskId::new(2));
+    root
+      .execute_command(TaskCommand::AddBlocker {
+        id: build,
+        blocker: design,
+      })
+      .unwrap();
+    let start = |force| TaskCommand::UpdateStatus {
+      id: build,
+      status: TaskStatus::InProgress,
+      force,
+    };
+
+    assert!(matches!(
+      root.execute_command(start(false)),
+      Err(crate::domain::Error::BlockedBy(x)) if x == design
+    ));
+    root.execute_command(start(true)).unwrap();
+
+    root
+      .execute_command(TaskCommand::UpdateStatus {
+        id: build,
+        status: TaskStatus::Queue,
+        force: false,
+      })
+      .unwrap();
+    root
+      .execute_command(TaskCommand::UpdateStatus {
+        id: design,
+        status: TaskStatus::Done,
+        force: false,
+      })
+      .unwrap();
+    root.execute_command(start(false)).unwrap();
+  }
+
+  #[test]
+  fn manage_checklist_items() {
+    let mut root = AggregateRoot::<Task>::default();
+    let id = create_task(&mut root);
+    for text in ["Draft", " Review ", "Publish"] {
+      root
+        .execute_command(TaskCommand::AddChecklistItem {
+          id,
+          text: text.to_string(),
+          checked: false,
+          position: None,
+        })
+        .unwrap();
+    }
+    root
+      .execute_command(TaskCommand::ToggleChecklistItem { id, item: 1 })
+      .unwrap();
+    root
+      .execute_command(TaskCommand::MoveChecklistItem {
+        id,
+        item: 3,
+        position: 0,
+      })
+      .unwrap();
+    let task = root.get_state("#1").unwrap();
+    let texts: Vec<_> = task.checklist.iter().map(|x| x.text.as_str()).collect();
+    assert_eq!(texts, vec!["Publish", "Draft", "Review"]);
+    assert_eq!(
+      task.checklist_progress(),
+      Some(ChecklistProgress { done: 1, total: 3 })
+    );
+
+    let before = task.clone();
+    let persisted = root
+      .execute_command(TaskCommand::RemoveChecklistItem { id, item: 1 })
+      .unwrap();
+    assert_eq!(root.get_state("#1").unwrap().checklist.len(), 2);
+    let command = compensating_command(Some(&before), &persisted.event, &root).unwrap();
+    root.execute_command(command).unwrap();
+    let task = root.get_state("#1").unwrap();
+    assert_eq!(task.checklist[1].text, "Draft");
+    assert!(task.checklist[1].checked);
+
+    assert!(matches!(
+      root.execute_command(TaskCommand::ToggleChecklistItem { id, item: 9 }),
+      Err(crate::domain::Error::ChecklistItemNotExists)
+    ));
+    assert!(matches!(
+      root.execute_command(TaskCommand::AddChecklistItem {
+        id,
+        text: " ".to_string(),
+        checked: false,
+        position: None,
+      }),
+      Err(crate::domain::Error::InvalidChecklistItem)
+    ));
+  }
 }
@@ -1,21 +1,43 @@
 use std::collections::HashMap;
 
-use crate::eventsourcing::{Command, Event, Persisted, Version};
+use chrono::Utc;
+
+use crate::eventsourcing::{Command, Event, Persisted, Timestamp, Version};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("version mismatch on {aggregate_id} (expected: {expected}, actual: {actual})")]
+pub struct VersionMismatch {
+  pub aggregate_id: String,
+  pub expected: Version,
+  pub actual: Version,
+}
 
 pub trait Aggregate: Sized + Send + Sync + Clone {
   type Command: Command;
   type Event: Event;
-  type Error: Send + Sync;
+  type Error: Send + Sync + From<VersionMismatch>;
 
   fn id(&self) -> String;
 
+  /// Another key the aggregate can be found with, e.g. a short number shown to users.
+  /// Unlike `id`, it may be given to another aggregate later.
+  fn alias(&self) -> Option<String> {
+    None
+  }
+
   fn handle_command(
     this: Option<&Self>,
     command: Self::Command,
     root: &AggregateRoot<Self>,
   ) -> Result<Self::Event, Self::Error>;
 
-  fn apply_event(this: Option<Self>, event: Self::Event) -> Result<(String, Option<Self>), Self::Error>;
+  /// `occurred_at` is when the command of the event was executed. Use it instead of the
+  /// clock, so replayed states are the same as the original ones.
+  fn apply_event(
+    this: Option<Self>,
+    event: Self::Event,
+    occurred_at: Timestamp,
+  ) -> Result<(String, Option<Self>), Self::Error>;
 }
 
 #[derive(Debug, Clone)]
@@ -25,6 +47,7 @@ where
 {
   pub states: HashMap<String, T>,
   pub versions: HashMap<String, Version>,
+  aliases: HashMap<String, String>,
 }
 
 impl<T> Default for AggregateRoot<T>
@@ -35,6 +58,7 @@ where
     Self {
       states: HashMap::new(),
       versions: HashMap::new(),
+      aliases: HashMap::new(),
     }
   }
 }
@@ -44,42 +68,66 @@ where
   T: Aggregate,
 {
   pub fn new(states: HashMap<String, T>, versions: HashMap<String, Version>) -> Self {
-    Self { states, versions }
+    let aliases = states
+      .iter()
+      .filter_map(|(id, state)| Some((state.alias()?, id.to_owned())))
+      .collect();
+    Self {
+      states,
+      versions,
+      aliases,
+    }
   }
 
   pub fn ids(&self) -> Vec<&String> {
     self.states.keys().collect::<Vec<_>>()
   }
 
+  /// Id of the aggregate `key` refers to, either its id or alias.
+  pub fn resolve_id<'a>(&'a self, key: &'a str) -> &'a str {
+    match self.states.contains_key(key) {
+      true => key,
+      false => self.aliases.get(key).map_or(key, |x| x.as_str()),
+    }
+  }
+
   pub fn get_state<K: AsRef<str>>(&self, id: K) -> Option<&T> {
-    self.states.get(id.as_ref())
+    self.states.get(self.resolve_id(id.as_ref()))
   }
 
   pub fn get_version<K: AsRef<str>>(&self, id: K) -> Option<&Version> {
-    self.versions.get(id.as_ref())
+    self.versions.get(self.resolve_id(id.as_ref()))
   }
 
+  /// Fails with `VersionMismatch` when the command expects another version of its aggregate.
   pub fn execute_command(&mut self, command: T::Command) -> Result<Persisted<T::Event>, T::Error> {
-    let id = command.aggregate_id().to_owned();
+    let id = command
+      .aggregate_id()
+      .map(|x| self.resolve_id(&x).to_owned());
+    if let (Some(id), Some(expected)) = (&id, command.expected_version()) {
+      let actual = self.versions.get(id).cloned().unwrap_or(0);
+      if actual != expected {
+        return Err(T::Error::from(VersionMismatch {
+          aggregate_id: id.to_owned(),
+          expected,
+          actual,
+        }));
+      }
+    }
     let state = match id {
       Some(id) => self.states.get(&id),
       None => None,
     };
     let event = T::handle_command(state, command, self)?;
-    let (id, state) = T::apply_event(state.cloned(), event.clone())?;
-    let version = self.versions.entry(id.to_owned()).or_insert(0);
-    *version += 1;
-    match state {
-      Some(state) => {
-        self.states.insert(id.to_owned(), state);
-      },
-      None => {
-        self.states.remove(&id);
-      }
-    }
+    let occurred_at = Utc::now().timestamp_millis();
+    let (id, state) = T::apply_event(state.cloned(), event.clone(), occurred_at)?;
+    let version = self.versions.get(&id).cloned().unwrap_or(0) + 1;
+    self.versions.insert(id.to_owned(), version);
+    self.put_state(&id, state);
     let persisted = Persisted {
-      aggregate_id: id.to_owned(),
-      version: *version,
+      aggregate_id: id,
+      version,
+      occurred_at,
       event,
     };
 
@@ -88,23 +136,39 @@ where
 
   pub fn save_events(&mut self, events: Vec<Persisted<T::Event>>) -> Result<(), T::Error> {
     for persisted in events {
-      let id = persisted.aggregate_id.to_owned();
-      let (id, state) = T::apply_event(self.states.get(&id).cloned(), persisted.event)?;
-      match state {
-        Some(state) => self.states.insert(id.to_owned(), state),
-        None => self.states.remove(&id),
-      };
-      self.versions.insert(id.to_owned(), persisted.version);
+      let state = self.get_state(&persisted.aggregate_id).cloned();
+      let (id, state) = T::apply_event(state, persisted.event, persisted.occurred_at)?;
+      self.put_state(&id, state);
+      self.versions.insert(id, persisted.version);
     }
 
     Ok(())
   }
+
+  fn put_state(&mut self, id: &str, state: Option<T>) {
+    if let Some(alias) = self.states.get(id).and_then(|x| x.alias()) {
+      if self.aliases.get(&alias).is_some_and(|x| x == id) {
+        self.aliases.remove(&alias);
+      }
+    }
+    match state {
+      Some(state) => {
+        if let Some(alias) = state.alias() {
+          self.aliases.insert(alias, id.to_owned());
+        }
+        self.states.insert(id.to_owned(), state);
+      }
+      None => {
+        self.states.remove(id);
+      }
+    }
+  }
 }
 
 #[cfg(test)]
 mod test {
   use crate::eventsourcing::dummy::{Todo, TodoCommand, TodoError, TodoEvent, TodoStatus};
-  use crate::eventsourcing::{AggregateRoot, Persisted};
+  use crate::eventsourcing::{AggregateRoot, Persisted, VersionMismatch};
 
   #[test]
   fn execute_command_and_returns_persisted_event() {
@@ -121,6 +185,7 @@ mod test {
       Persisted {
         aggregate_id: "todo_0".to_string(),
         version: 1,
+        occurred_at: persisted.occurred_at,
         event: TodoEvent::TodoCreated {
           id: "todo_0".to_string(),
           title: "Drink soda".to_string(),
@@ -159,6 +224,7 @@ mod test {
     let command3 = TodoCommand::UpdateTodoStatus {
       id: "todo_0".to_string(),
       status: TodoStatus::Done,
+      expected_version: None,
     };
     todo_root.execute_command(command3).unwrap();
 
@@ -209,12 +275,64 @@ mod test {
     assert_eq!(err, TodoError::AlreadyExists);
   }
 
+  #[test]
+  fn execute_command_on_expected_version() {
+    let mut todo_root: AggregateRoot<Todo> = AggregateRoot::default();
+    let command1 = TodoCommand::CreateTodo {
+      id: "todo_0".to_string(),
+      title: "Eat rice".to_string(),
+      status: None,
+    };
+    todo_root.execute_command(command1).unwrap();
+
+    let command2 = TodoCommand::UpdateTodoStatus {
+      id: "todo_0".to_string(),
+      status: TodoStatus::Done,
+      expected_version: Some(1),
+    };
+    todo_root.execute_command(command2).unwrap();
+    assert_eq!(todo_root.get_version("todo_0").unwrap(), &2);
+  }
+
+  #[test]
+  fn error_when_version_mismatch() {
+    let mut todo_root: AggregateRoot<Todo> = AggregateRoot::default();
+    let command1 = TodoCommand::CreateTodo {
+      id: "todo_0".to_string(),
+      title: "Eat rice".to_string(),
+      status: None,
+    };
+    todo_root.execute_command(command1).unwrap();
+
+    let command2 = TodoCommand::UpdateTodoStatus {
+      id: "todo_0".to_string(),
+      status: TodoStatus::Done,
+      expected_version: Some(3),
+    };
+    let err = todo_root.execute_command(command2).unwrap_err();
+
+    assert_eq!(
+      err,
+      TodoError::VersionMismatch(VersionMismatch {
+        aggregate_id: "todo_0".to_string(),
+        expected: 3,
+        actual: 1,
+      })
+    );
+    assert_eq!(
+      todo_root.get_state("todo_0").unwrap().status,
+      TodoStatus::Todo
+    );
+    assert_eq!(todo_root.get_version("todo_0").unwrap(), &1);
+  }
+
   #[test]
   fn save_events() {
     let events = vec![
       Persisted {
         aggregate_id: "todo_0".to_string(),
         version: 1,
+        occurred_at: 0,
         event: TodoEvent::TodoCreated {
           id: "todo_0".to_string(),
           title: "Drink soda".to_string(),
@@ -224,6 +342,7 @@ mod test {
       Persisted {
         aggregate_id: "todo_0".to_string(),
         version: 2,
+        occurred_at: 0,
         event: TodoEvent::TodoTitleUpdated {
           title: "Coding".to_string(),
         },
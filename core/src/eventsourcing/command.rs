@@ -1,4 +1,12 @@
+use crate::eventsourcing::Version;
+
 pub trait Command: Send + Sync + Clone {
   fn name(&self) -> &'static str;
   fn aggregate_id(&self) -> Option<String>;
+
+  /// Version the aggregate must still be on, so the command does not overwrite changes made
+  /// after its issuer read the state. Aggregate not exists yet is on version 0.
+  fn expected_version(&self) -> Option<Version> {
+    None
+  }
 }
@@ -1,5 +1,5 @@
 use crate::eventsourcing::{
-  Aggregate, AggregateRoot, Eventstore, Persisted, Snapshot, VersionSelect,
+  Aggregate, AggregateRoot, Eventstore, Persisted, Snapshot, Version, VersionSelect,
 };
 
 pub async fn get_unsaved_events<T, E>(
@@ -63,3 +63,183 @@ where
     .map_err(LoadAggregateError::Snapshot)?;
   Ok(root)
 }
+
+/// Replays every event on an empty root and replaces the snapshot with it, so states
+/// pick up changes of `apply_event`. The snapshot is not touched when any event fails.
+pub async fn rebuild<T, E, S>(
+  eventstore: &E,
+  snapshot: &S,
+) -> Result<AggregateRoot<T>, LoadAggregateError<T::Error, E::Error, S::Error>>
+where
+  T: Aggregate,
+  E: Eventstore<Event = T::Event>,
+  S: Snapshot<T>,
+{
+  let events = eventstore
+    .read_all()
+    .await
+    .map_err(LoadAggregateError::Eventstore)?;
+  let mut root = AggregateRoot::default();
+  root
+    .save_events(events)
+    .map_err(LoadAggregateError::Aggregate)?;
+  snapshot
+    .replace(&root)
+    .await
+    .map_err(LoadAggregateError::Snapshot)?;
+  Ok(root)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError<E, EE> {
+  #[error("aggregate error: {0}")]
+  Aggregate(#[source] E),
+
+  #[error("eventstore error: {0}")]
+  Eventstore(#[source] EE),
+}
+
+/// Rebuilds state of an aggregate from history, replaying its events until `version`.
+/// Returns `None` when the aggregate did not exist on the version.
+pub async fn replay_aggregate<T, E>(
+  eventstore: &E,
+  aggregate_id: &str,
+  version: Version,
+) -> Result<Option<T>, ReplayError<T::Error, E::Error>>
+where
+  T: Aggregate,
+  E: Eventstore<Event = T::Event>,
+{
+  let events = eventstore
+    .read(aggregate_id.to_owned(), VersionSelect::All)
+    .await
+    .map_err(ReplayError::Eventstore)?
+    .into_iter()
+    .filter(|x| x.version <= version)
+    .collect::<Vec<_>>();
+  let mut root = AggregateRoot::<T>::default();
+  root.save_events(events).map_err(ReplayError::Aggregate)?;
+  Ok(root.states.remove(aggregate_id))
+}
+
+#[cfg(test)]
+mod tests {
+  use geeks_tracker_testing::git::FixtureRepository;
+
+  use crate::eventsourcing::dummy::{Todo, TodoCommand, TodoEvent, TodoSnapshot, TodoStatus};
+  use crate::eventsourcing::git::GitEventstore;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn replay_aggregate_until_version() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::new(fixture.path());
+    let mut root = AggregateRoot::<Todo>::default();
+    let commands = vec![
+      TodoCommand::CreateTodo {
+        id: "todo1".to_string(),
+        title: "Drink coffee".to_string(),
+        status: None,
+      },
+      TodoCommand::UpdateTodoTitle {
+        id: "todo1".to_string(),
+        title: "Eat pizza".to_string(),
+      },
+      TodoCommand::UpdateTodoStatus {
+        id: "todo1".to_string(),
+        status: TodoStatus::Done,
+        expected_version: None,
+      },
+    ];
+    for command in commands {
+      let persisted = root.execute_command(command).unwrap();
+      eventstore.append(vec![persisted]).await.unwrap();
+    }
+
+    let todo: Todo = replay_aggregate(&eventstore, "todo1", 2)
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(todo.title, "Eat pizza");
+    assert_eq!(todo.status, TodoStatus::Todo);
+
+    let todo: Option<Todo> = replay_aggregate(&eventstore, "todo1", 0).await.unwrap();
+    assert!(todo.is_none());
+  }
+
+  #[tokio::test]
+  async fn rebuild_snapshot_from_all_events() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::new(fixture.path());
+    let snapshot = TodoSnapshot::new(fixture.path());
+    let mut root = AggregateRoot::<Todo>::default();
+    let commands = vec![
+      TodoCommand::CreateTodo {
+        id: "todo1".to_string(),
+        title: "Drink coffee".to_string(),
+        status: None,
+      },
+      TodoCommand::CreateTodo {
+        id: "todo2".to_string(),
+        title: "Eat pizza".to_string(),
+        status: None,
+      },
+    ];
+    for command in commands {
+      let persisted = root.execute_command(command).unwrap();
+      eventstore.append(vec![persisted]).await.unwrap();
+    }
+    // stale snapshot, which has a todo without events and misses another.
+    let mut stale = root.clone();
+    stale.states.remove("todo2");
+    stale.states.get_mut("todo1").unwrap().title = "Edited".to_string();
+    stale
+      .states
+      .insert("todo3".to_string(), root.states["todo1"].clone());
+    stale.versions.insert("todo3".to_string(), 1);
+    snapshot.save(&stale).await.unwrap();
+
+    let rebuilt: AggregateRoot<Todo> = rebuild(&eventstore, &snapshot).await.unwrap();
+    let loaded = snapshot.load().await.unwrap();
+    for root in [rebuilt, loaded] {
+      let mut ids = root.ids();
+      ids.sort();
+      assert_eq!(ids, vec!["todo1", "todo2"]);
+      assert_eq!(root.get_state("todo1").unwrap().title, "Drink coffee");
+    }
+  }
+
+  #[tokio::test]
+  async fn keep_snapshot_when_rebuild_fails() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::new(fixture.path());
+    let snapshot = TodoSnapshot::new(fixture.path());
+    let mut root = AggregateRoot::<Todo>::default();
+    let persisted = root
+      .execute_command(TodoCommand::CreateTodo {
+        id: "todo1".to_string(),
+        title: "Drink coffee".to_string(),
+        status: None,
+      })
+      .unwrap();
+    eventstore.append(vec![persisted]).await.unwrap();
+    snapshot.save(&root).await.unwrap();
+    // an event of a todo never created can't be applied.
+    eventstore
+      .append(vec![Persisted {
+        aggregate_id: "todo2".to_string(),
+        version: 1,
+        occurred_at: 0,
+        event: TodoEvent::TodoTitleUpdated {
+          title: "Eat pizza".to_string(),
+        },
+      }])
+      .await
+      .unwrap();
+
+    let result: Result<AggregateRoot<Todo>, _> = rebuild(&eventstore, &snapshot).await;
+    assert!(matches!(result, Err(LoadAggregateError::Aggregate(_))));
+    assert_eq!(snapshot.load().await.unwrap().ids(), vec!["todo1"]);
+  }
+}
@@ -4,13 +4,12 @@ use std::path::{Path, PathBuf};
 use std::str::{from_utf8, Utf8Error};
 
 use async_trait::async_trait;
-use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::{from_str, to_value};
-use tokio::fs::{read, write};
+use tokio::fs::{read, rename, write};
 
 use crate::eventsourcing::{
-  Aggregate, AggregateRoot, Command, Event, Snapshot, Timestamp, Version,
+  Aggregate, AggregateRoot, Command, Event, Snapshot, Timestamp, Version, VersionMismatch,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -54,6 +53,7 @@ pub enum TodoCommand {
   UpdateTodoStatus {
     id: String,
     status: TodoStatus,
+    expected_version: Option<Version>,
   },
 }
 
@@ -74,6 +74,15 @@ impl Command for TodoCommand {
     }
     .map(|x| x.to_owned())
   }
+
+  fn expected_version(&self) -> Option<Version> {
+    match self {
+      TodoCommand::UpdateTodoStatus {
+        expected_version, ..
+      } => *expected_version,
+      _ => None,
+    }
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -92,6 +101,8 @@ pub enum TodoError {
   AlreadyExists,
   #[error("Todo not exists")]
   NotExists,
+  #[error(transparent)]
+  VersionMismatch(#[from] VersionMismatch),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,9 +148,8 @@ impl Aggregate for Todo {
   fn apply_event(
     this: Option<Self>,
     event: Self::Event,
+    occurred_at: Timestamp,
   ) -> Result<(String, Option<Self>), Self::Error> {
-    let timestamp = Utc::now().timestamp();
-
     match event {
       TodoEvent::TodoCreated { id, title, status } => {
         if this.is_some() {
@@ -151,8 +161,8 @@ impl Aggregate for Todo {
             id,
             title,
             status,
-            created_at: timestamp,
-            updated_at: timestamp,
+            created_at: occurred_at,
+            updated_at: occurred_at,
           }),
         ))
       }
@@ -232,4 +242,15 @@ impl Snapshot<Todo> for TodoSnapshot {
 
     Ok(())
   }
+
+  /// Writes next to the snapshot file first, then renames it over the file.
+  async fn replace(&self, root: &AggregateRoot<Todo>) -> Result<(), Self::Error> {
+    let staging = Self {
+      file_path: self.file_path.with_extension("staging"),
+    };
+    staging.save(root).await?;
+    rename(&staging.file_path, &self.file_path).await?;
+
+    Ok(())
+  }
 }
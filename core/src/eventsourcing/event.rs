@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
-use crate::eventsourcing::Version;
+use crate::eventsourcing::{Timestamp, Upcaster, Version};
 
 pub trait Event: Send + Sync + Clone {
   fn name(&self) -> &'static str;
+
+  /// Upcasters of stored events, the first one from schema version 1 to 2 and so on.
+  /// Add one whenever the shape of the event changes.
+  fn upcasters() -> &'static [Upcaster] {
+    &[]
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -16,5 +22,7 @@ where
 {
   pub aggregate_id: String,
   pub version: Version,
+  /// When the command was executed (milliseconds), so replays never depend on the clock.
+  pub occurred_at: Timestamp,
   pub event: T,
 }
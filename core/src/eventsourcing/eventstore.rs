@@ -19,5 +19,8 @@ pub trait Eventstore: Send + Sync {
     select: VersionSelect,
   ) -> Result<Vec<Persisted<Self::Event>>, Self::Error>;
 
+  /// Reads every event of every aggregate, in the order they were appended.
+  async fn read_all(&self) -> Result<Vec<Persisted<Self::Event>>, Self::Error>;
+
   async fn append(&self, events: Vec<Persisted<Self::Event>>) -> Result<(), Self::Error>;
 }
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// Body of `[event]` commit. A batch holds several events written atomically,
+/// while a single event is written as it is (also the format of old commits).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EventCommitBody<E> {
+  Batch {
+    events: Vec<E>,
+    /// Commit which this one is rewritten from, when merged by sync.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    origin: Option<String>,
+  },
+  Single(E),
+}
+
+impl<E> EventCommitBody<E> {
+  pub fn new(mut events: Vec<E>) -> Self {
+    match events.len() {
+      1 => Self::Single(events.remove(0)),
+      _ => Self::Batch {
+        events,
+        origin: None,
+      },
+    }
+  }
+
+  pub fn with_origin(events: Vec<E>, origin: String) -> Self {
+    Self::Batch {
+      events,
+      origin: Some(origin),
+    }
+  }
+
+  pub fn origin(&self) -> Option<&str> {
+    match self {
+      Self::Batch { origin, .. } => origin.as_deref(),
+      Self::Single(_) => None,
+    }
+  }
+
+  pub fn into_events(self) -> Vec<E> {
+    match self {
+      Self::Batch { events, .. } => events,
+      Self::Single(event) => vec![event],
+    }
+  }
+}
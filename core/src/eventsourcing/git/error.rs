@@ -0,0 +1,23 @@
+use git2::Oid;
+
+use crate::eventsourcing::{UpcastError, Version};
+use crate::git;
+
+#[derive(thiserror::Error, Debug)]
+pub enum GitEventstoreError {
+  #[error(transparent)]
+  Git(#[from] git::Error),
+  #[error("version {version} of {aggregate_id} already exists")]
+  VersionAlreadyExists {
+    aggregate_id: String,
+    version: Version,
+  },
+  #[error("invalid event in commit {oid}: {source}")]
+  InvalidEvent { oid: Oid, source: UpcastError },
+}
+
+impl From<git2::Error> for GitEventstoreError {
+  fn from(e: git2::Error) -> Self {
+    Self::Git(git::Error::from(e))
+  }
+}
@@ -1,15 +1,16 @@
+use std::collections::HashSet;
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use async_trait::async_trait;
-use git2::Repository;
+use git2::{Oid, Repository};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use serde_json::{from_str, to_string};
+use serde_json::{from_str, to_string, Value};
 
-use crate::eventsourcing::git::SNAPSHOT_MSG;
-use crate::eventsourcing::{Event, Eventstore, Persisted, VersionSelect};
-use crate::git;
+use crate::eventsourcing::git::{EventCommitBody, EventIndex, GitEventstoreError, SNAPSHOT_MSG};
+use crate::eventsourcing::{Event, Eventstore, Persisted, StoredEvent, VersionSelect};
 use crate::git::{commit, CommitInfo, CommitMessage, CommitReader};
 
 pub const EVENT_MSG: &str = "[event]";
@@ -19,6 +20,7 @@ where
   T: Event,
 {
   repo_path: PathBuf,
+  index: Mutex<Option<EventIndex>>,
   _event: PhantomData<T>,
 }
 
@@ -29,43 +31,117 @@ where
   pub fn new(repo_path: &Path) -> Self {
     Self {
       repo_path: repo_path.to_path_buf(),
+      index: Mutex::new(None),
       _event: PhantomData,
     }
   }
 
-  fn event_to_commit_message(persisted: Persisted<T>) -> CommitMessage {
+  /// Message of `[event]` commit. `origin` is the commit it is rewritten from, if any.
+  pub fn events_to_commit_message(events: Vec<Persisted<T>>, origin: Option<Oid>) -> CommitMessage {
+    let event_names = events
+      .iter()
+      .map(|x| x.event.name())
+      .collect::<Vec<_>>()
+      .join(", ");
+    let events = events.into_iter().map(StoredEvent::from).collect();
+    let body = match origin {
+      Some(origin) => EventCommitBody::with_origin(events, origin.to_string()),
+      None => EventCommitBody::new(events),
+    };
     CommitMessage {
       subject: format!(
-        "{prefix} {event_name}",
+        "{prefix} {event_names}",
         prefix = EVENT_MSG,
-        event_name = persisted.event.name()
+        event_names = event_names
       ),
-      body: to_string(&persisted).unwrap(),
+      body: to_string(&body).unwrap(),
     }
   }
 
-  fn commit_to_event(commit: CommitInfo) -> Option<Persisted<T>> {
+  /// Reads all events in the commit, in the order they were appended.
+  /// Events of older schema versions are upcasted, and the commit is an error when any of
+  /// them still can't be read. Events stored without occurrence time get the commit time.
+  pub fn commit_to_events(commit: CommitInfo) -> Result<Vec<Persisted<T>>, GitEventstoreError> {
     if !commit.message.subject.contains(EVENT_MSG) {
-      return None;
+      return Ok(Vec::new());
     }
 
-    match from_str(commit.message.body.trim()) {
-      Ok(x) => Some(x),
-      Err(_) => None,
-    }
+    let invalid = |source| GitEventstoreError::InvalidEvent {
+      oid: commit.id,
+      source,
+    };
+    let body: EventCommitBody<StoredEvent<Value>> =
+      from_str(commit.message.body.trim()).map_err(|e| invalid(e.into()))?;
+    body
+      .into_events()
+      .into_iter()
+      .map(|stored| stored.upcast(commit.time * 1000).map_err(invalid))
+      .collect()
   }
 
-  pub async fn read_until_snapshot(&self) -> Result<Vec<Persisted<T>>, git::Error> {
+  pub async fn read_until_snapshot(&self) -> Result<Vec<Persisted<T>>, GitEventstoreError> {
     let repo = Repository::open(&self.repo_path)?;
-    let mut events: Vec<_> = CommitReader::new(&repo)?
+    let commits = CommitReader::new(&repo)?
       .start_on_head()
       .end_when(|x| x.message.subject.contains(SNAPSHOT_MSG))
       .flatten()
-      .filter_map(GitEventstore::commit_to_event)
-      .collect();
+      .map(GitEventstore::commit_to_events)
+      .collect::<Result<Vec<_>, _>>()?;
 
-    events.reverse();
-    Ok(events)
+    Ok(commits.into_iter().rev().flatten().collect())
+  }
+
+  /// Reads events committed after `oid`. Useful to catch up events written by another process.
+  pub async fn read_after(&self, oid: Oid) -> Result<Vec<Persisted<T>>, GitEventstoreError> {
+    let repo = Repository::open(&self.repo_path)?;
+    let commits = CommitReader::new(&repo)?
+      .start_on_head()
+      .hide_oid(oid)
+      .flatten()
+      .map(GitEventstore::commit_to_events)
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(commits.into_iter().rev().flatten().collect())
+  }
+
+  /// Runs `f` with event index caught up with HEAD. Index is kept in memory between calls.
+  fn with_index<F, R>(&self, repo: &Repository, f: F) -> Result<R, GitEventstoreError>
+  where
+    F: FnOnce(&EventIndex) -> R,
+  {
+    let mut cached = self.index.lock().expect("event index lock poisoned");
+    let index = match cached.take() {
+      Some(mut index) => {
+        if index.update(repo)? {
+          index.save(repo)?;
+        }
+        index
+      }
+      None => EventIndex::open(repo)?,
+    };
+    let result = f(&index);
+    *cached = Some(index);
+    Ok(result)
+  }
+
+  fn ensure_new_versions(
+    &self,
+    repo: &Repository,
+    events: &[Persisted<T>],
+  ) -> Result<(), GitEventstoreError> {
+    self.with_index(repo, |index| {
+      let mut versions = HashSet::new();
+      for event in events {
+        let exists = index.contains(&event.aggregate_id, event.version);
+        if exists || !versions.insert((event.aggregate_id.to_owned(), event.version)) {
+          return Err(GitEventstoreError::VersionAlreadyExists {
+            aggregate_id: event.aggregate_id.to_owned(),
+            version: event.version,
+          });
+        }
+      }
+      Ok(())
+    })?
   }
 }
 
@@ -75,7 +151,7 @@ where
   T: Event + Serialize + DeserializeOwned,
 {
   type Event = T;
-  type Error = git::Error;
+  type Error = GitEventstoreError;
 
   async fn read(
     &self,
@@ -83,30 +159,46 @@ where
     select: VersionSelect,
   ) -> Result<Vec<Persisted<Self::Event>>, Self::Error> {
     let repo = Repository::open(&self.repo_path)?;
-    let mut events: Vec<_> = CommitReader::new(&repo)?
+    let mut oids = self.with_index(&repo, |index| index.find(&aggregate_id, select))?;
+    // a batch commit may hold several versions of the aggregate.
+    oids.dedup();
+    let mut events = Vec::with_capacity(oids.len());
+    for oid in oids {
+      let commit = repo.find_commit(oid).map(CommitInfo::from)?;
+      events.extend(
+        GitEventstore::commit_to_events(commit)?
+          .into_iter()
+          .filter(|x| x.aggregate_id == aggregate_id)
+          .filter(|x| match select {
+            VersionSelect::All => true,
+            VersionSelect::From(v) => x.version >= v,
+          }),
+      );
+    }
+
+    Ok(events)
+  }
+
+  /// Reads from the root commit, ignoring snapshots.
+  async fn read_all(&self) -> Result<Vec<Persisted<Self::Event>>, Self::Error> {
+    let repo = Repository::open(&self.repo_path)?;
+    let commits = CommitReader::new(&repo)?
       .start_on_head()
       .flatten()
-      .filter_map(GitEventstore::commit_to_event)
-      .filter(|event| event.aggregate_id == aggregate_id)
-      .filter(|event| match select {
-        VersionSelect::All => true,
-        VersionSelect::From(v) => event.version >= v,
-      })
-      .collect();
-
-    events.reverse();
-    Ok(events)
+      .map(GitEventstore::commit_to_events)
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(commits.into_iter().rev().flatten().collect())
   }
 
   async fn append(&self, events: Vec<Persisted<Self::Event>>) -> Result<(), Self::Error> {
     let repo = Repository::open(&self.repo_path)?;
-    let commit_messages = events
-      .into_iter()
-      .map(GitEventstore::event_to_commit_message);
-
-    for message in commit_messages {
-      commit(&repo, message)?;
+    self.ensure_new_versions(&repo, &events)?;
+    if events.is_empty() {
+      return Ok(());
     }
+    // all events are written in one commit, so they are never half-applied.
+    commit(&repo, GitEventstore::events_to_commit_message(events, None))?;
 
     Ok(())
   }
@@ -117,8 +209,9 @@ mod tests {
   use geeks_tracker_testing::git::FixtureRepository;
 
   use crate::eventsourcing::dummy::{TodoEvent, TodoStatus};
-  use crate::eventsourcing::git::GitEventstore;
+  use crate::eventsourcing::git::{GitEventstore, GitEventstoreError};
   use crate::eventsourcing::{Event, Eventstore, Persisted, VersionSelect};
+  use crate::git::{commit, get_head, CommitMessage};
 
   #[tokio::test]
   async fn should_read_events() {
@@ -138,11 +231,13 @@ mod tests {
         Persisted {
           aggregate_id: "todo1".to_string(),
           version: 2,
+          occurred_at: 0,
           event: event2,
         },
         Persisted {
           aggregate_id: "todo1".to_string(),
           version: 1,
+          occurred_at: 0,
           event: event1,
         },
       ])
@@ -157,4 +252,215 @@ mod tests {
     assert_eq!(events[0].event.name(), "TodoTitleUpdated");
     assert_eq!(events[1].event.name(), "TodoCreated");
   }
+
+  #[tokio::test]
+  async fn should_read_events_after_oid() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::new(fixture.path());
+    eventstore
+      .append(vec![Persisted {
+        aggregate_id: "todo1".to_string(),
+        version: 1,
+        occurred_at: 0,
+        event: TodoEvent::TodoCreated {
+          id: "todo1".to_string(),
+          title: "Drink coffee".to_string(),
+          status: TodoStatus::InProgress,
+        },
+      }])
+      .await
+      .unwrap();
+    let head = get_head(&fixture.repo()).unwrap();
+    eventstore
+      .append(vec![Persisted {
+        aggregate_id: "todo1".to_string(),
+        version: 2,
+        occurred_at: 0,
+        event: TodoEvent::TodoTitleUpdated {
+          title: "Eat pizza".to_string(),
+        },
+      }])
+      .await
+      .unwrap();
+
+    let events = eventstore.read_after(head).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].version, 2);
+  }
+
+  #[tokio::test]
+  async fn should_not_append_existing_version() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::new(fixture.path());
+    let created = Persisted {
+      aggregate_id: "todo1".to_string(),
+      version: 1,
+      occurred_at: 0,
+      event: TodoEvent::TodoCreated {
+        id: "todo1".to_string(),
+        title: "Drink coffee".to_string(),
+        status: TodoStatus::InProgress,
+      },
+    };
+    eventstore.append(vec![created.clone()]).await.unwrap();
+
+    let err = eventstore.append(vec![created]).await.unwrap_err();
+    assert!(matches!(
+      err,
+      GitEventstoreError::VersionAlreadyExists { version: 1, .. }
+    ));
+    let events = eventstore
+      .read("todo1".to_string(), VersionSelect::All)
+      .await
+      .unwrap();
+    assert_eq!(events.len(), 1);
+  }
+
+  #[tokio::test]
+  async fn should_append_events_in_one_commit() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::new(fixture.path());
+    eventstore
+      .append(vec![
+        Persisted {
+          aggregate_id: "todo1".to_string(),
+          version: 1,
+          occurred_at: 0,
+          event: TodoEvent::TodoCreated {
+            id: "todo1".to_string(),
+            title: "Drink coffee".to_string(),
+            status: TodoStatus::InProgress,
+          },
+        },
+        Persisted {
+          aggregate_id: "todo2".to_string(),
+          version: 1,
+          occurred_at: 0,
+          event: TodoEvent::TodoCreated {
+            id: "todo2".to_string(),
+            title: "Eat pizza".to_string(),
+            status: TodoStatus::Todo,
+          },
+        },
+        Persisted {
+          aggregate_id: "todo1".to_string(),
+          version: 2,
+          occurred_at: 0,
+          event: TodoEvent::TodoStatusUpdated {
+            status: TodoStatus::Done,
+          },
+        },
+      ])
+      .await
+      .unwrap();
+
+    let repo = fixture.repo();
+    let head = repo.find_commit(get_head(&repo).unwrap()).unwrap();
+    assert_eq!(head.parent_count(), 0);
+    assert_eq!(
+      head.summary(),
+      Some("[event] TodoCreated, TodoCreated, TodoStatusUpdated")
+    );
+
+    let events = eventstore
+      .read("todo1".to_string(), VersionSelect::All)
+      .await
+      .unwrap();
+    assert_eq!(
+      events.iter().map(|x| x.version).collect::<Vec<_>>(),
+      vec![1, 2]
+    );
+    let events = eventstore
+      .read("todo1".to_string(), VersionSelect::From(2))
+      .await
+      .unwrap();
+    assert_eq!(events.len(), 1);
+    let events = eventstore.read_until_snapshot().await.unwrap();
+    assert_eq!(
+      events
+        .iter()
+        .map(|x| (x.aggregate_id.as_str(), x.version))
+        .collect::<Vec<_>>(),
+      vec![("todo1", 1), ("todo2", 1), ("todo1", 2)]
+    );
+  }
+
+  #[tokio::test]
+  async fn should_read_single_event_commits() {
+    let fixture = FixtureRepository::default();
+    let repo = fixture.repo();
+    // format of commits written before batch commits and occurrence time.
+    let oid = commit(
+      &repo,
+      CommitMessage {
+        subject: "[event] TodoCreated".to_string(),
+        body: r#"{"aggregateId":"todo1","version":1,"event":{"name":"TodoCreated","id":"todo1","title":"Drink coffee","status":"todo"}}"#.to_string(),
+      },
+    )
+    .unwrap();
+    let eventstore = GitEventstore::<TodoEvent>::new(fixture.path());
+    eventstore
+      .append(vec![Persisted {
+        aggregate_id: "todo1".to_string(),
+        version: 2,
+        occurred_at: 0,
+        event: TodoEvent::TodoTitleUpdated {
+          title: "Eat pizza".to_string(),
+        },
+      }])
+      .await
+      .unwrap();
+
+    let events = eventstore
+      .read("todo1".to_string(), VersionSelect::All)
+      .await
+      .unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].event.name(), "TodoCreated");
+    assert_eq!(events[1].event.name(), "TodoTitleUpdated");
+    let time = repo.find_commit(oid).unwrap().time().seconds();
+    assert_eq!(events[0].occurred_at, time * 1000);
+    assert_eq!(events[1].occurred_at, 0);
+  }
+
+  #[tokio::test]
+  async fn should_error_on_unreadable_event() {
+    let fixture = FixtureRepository::default();
+    let repo = fixture.repo();
+    let oid = commit(
+      &repo,
+      CommitMessage {
+        subject: "[event] TodoCreated".to_string(),
+        body: r#"{"aggregateId":"todo1","version":1,"schema":2,"event":{"name":"TodoCreated"}}"#
+          .to_string(),
+      },
+    )
+    .unwrap();
+    let eventstore = GitEventstore::<TodoEvent>::new(fixture.path());
+
+    let err = eventstore
+      .read("todo1".to_string(), VersionSelect::All)
+      .await
+      .unwrap_err();
+    assert!(matches!(err, GitEventstoreError::InvalidEvent { oid: x, .. } if x == oid));
+
+    // a body which is not an event at all is not skipped by the index either.
+    let fixture = FixtureRepository::default();
+    let repo = fixture.repo();
+    let oid = commit(
+      &repo,
+      CommitMessage {
+        subject: "[event] TodoCreated".to_string(),
+        body: "not an event".to_string(),
+      },
+    )
+    .unwrap();
+    let eventstore = GitEventstore::<TodoEvent>::new(fixture.path());
+
+    let err = eventstore
+      .read("todo1".to_string(), VersionSelect::All)
+      .await
+      .unwrap_err();
+    assert!(matches!(err, GitEventstoreError::InvalidEvent { oid: x, .. } if x == oid));
+  }
 }
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+
+use crate::eventsourcing::git::{EventCommitBody, GitEventstoreError, EVENT_MSG};
+use crate::eventsourcing::{Version, VersionSelect};
+use crate::git;
+use crate::git::{get_head, CommitInfo, CommitReader};
+
+const INDEX_FILE: &str = "geeks-tracker/event-index.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventIndexEntry {
+  pub version: Version,
+  pub oid: String,
+}
+
+/// Maps aggregate id and version to the commit which holds the event.
+///
+/// Index is derived from commit history and cached inside git dir (not in working dir,
+/// so snapshot commits never include it). It is caught up from the last indexed head,
+/// and rebuilt from scratch when the cache is missing, broken or history is rewritten.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventIndex {
+  head: Option<String>,
+  /// entries of each aggregate, in commit order.
+  aggregates: HashMap<String, Vec<EventIndexEntry>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexedEvent {
+  aggregate_id: String,
+  version: Version,
+}
+
+impl EventIndex {
+  fn filepath(repo: &Repository) -> PathBuf {
+    repo.path().join(INDEX_FILE)
+  }
+
+  /// Loads cached index, or empty index when the cache is missing or broken.
+  pub fn load(repo: &Repository) -> Self {
+    fs::read_to_string(Self::filepath(repo))
+      .ok()
+      .and_then(|x| serde_json::from_str(&x).ok())
+      .unwrap_or_default()
+  }
+
+  pub fn save(&self, repo: &Repository) -> Result<(), git::Error> {
+    let filepath = Self::filepath(repo);
+    if let Some(dirname) = filepath.parent() {
+      fs::create_dir_all(dirname)?;
+    }
+    // write on temp file and rename, not to leave half-written index.
+    let temp = filepath.with_extension("json.tmp");
+    let raw = serde_json::to_string(self).map_err(|e| git::Error::Generic(e.to_string()))?;
+    fs::write(&temp, raw)?;
+    fs::rename(&temp, &filepath)?;
+    Ok(())
+  }
+
+  /// Loads cached index and catches up with HEAD. Saves the cache when changed.
+  pub fn open(repo: &Repository) -> Result<Self, GitEventstoreError> {
+    let mut index = Self::load(repo);
+    if index.update(repo)? {
+      index.save(repo)?;
+    }
+    Ok(index)
+  }
+
+  /// Indexes commits after the last indexed head. Returns whether index is changed.
+  /// `[event]` commit whose body can't be read is an error, not skipped.
+  pub fn update(&mut self, repo: &Repository) -> Result<bool, GitEventstoreError> {
+    let head = match get_head(repo) {
+      Ok(head) => head,
+      Err(_) => return Ok(false),
+    };
+    let indexed = self.head.as_ref().and_then(|x| Oid::from_str(x).ok());
+    if indexed == Some(head) {
+      return Ok(false);
+    }
+
+    let reader = CommitReader::new(repo)?.start_on_head();
+    let reader = match indexed {
+      Some(indexed) if repo.graph_descendant_of(head, indexed).unwrap_or(false) => {
+        reader.hide_oid(indexed)
+      }
+      _ => {
+        self.aggregates.clear();
+        reader
+      }
+    };
+    let mut commits = reader.collect::<Result<Vec<_>, _>>()?;
+    commits.reverse();
+    for commit in commits {
+      self.insert(&commit)?;
+    }
+    self.head = Some(head.to_string());
+    Ok(true)
+  }
+
+  fn insert(&mut self, commit: &CommitInfo) -> Result<(), GitEventstoreError> {
+    if !commit.message.subject.contains(EVENT_MSG) {
+      return Ok(());
+    }
+    let body = serde_json::from_str::<EventCommitBody<IndexedEvent>>(commit.message.body.trim())
+      .map_err(|e| GitEventstoreError::InvalidEvent {
+        oid: commit.id,
+        source: e.into(),
+      })?;
+    for event in body.into_events() {
+      self
+        .aggregates
+        .entry(event.aggregate_id)
+        .or_default()
+        .push(EventIndexEntry {
+          version: event.version,
+          oid: commit.id.to_string(),
+        });
+    }
+    Ok(())
+  }
+
+  pub fn contains(&self, aggregate_id: &str, version: Version) -> bool {
+    self
+      .aggregates
+      .get(aggregate_id)
+      .map(|x| x.iter().any(|entry| entry.version == version))
+      .unwrap_or(false)
+  }
+
+  /// Returns oids of events in commit order.
+  pub fn find(&self, aggregate_id: &str, select: VersionSelect) -> Vec<Oid> {
+    self
+      .aggregates
+      .get(aggregate_id)
+      .map(|entries| {
+        entries
+          .iter()
+          .filter(|entry| match select {
+            VersionSelect::All => true,
+            VersionSelect::From(v) => entry.version >= v,
+          })
+          .filter_map(|entry| Oid::from_str(&entry.oid).ok())
+          .collect()
+      })
+      .unwrap_or_default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use geeks_tracker_testing::git::FixtureRepository;
+
+  use crate::git::{commit, CommitMessage};
+
+  use super::*;
+
+  fn commit_event(repo: &Repository, aggregate_id: &str, version: Version) -> Oid {
+    let message = CommitMessage {
+      subject: format!("{} test", EVENT_MSG),
+      body: format!(
+        r#"{{"aggregateId":"{}","version":{},"event":{{}}}}"#,
+        aggregate_id, version
+      ),
+    };
+    commit(repo, message).unwrap()
+  }
+
+  #[test]
+  fn should_index_events_by_aggregate() {
+    let fixture = FixtureRepository::default();
+    let repo = fixture.repo();
+    let a1 = commit_event(&repo, "a", 1);
+    commit(&repo, "[snapshot]").unwrap();
+    let b1 = commit_event(&repo, "b", 1);
+    let a2 = commit_event(&repo, "a", 2);
+
+    let index = EventIndex::open(&repo).unwrap();
+    assert_eq!(index.find("a", VersionSelect::All), vec![a1, a2]);
+    assert_eq!(index.find("a", VersionSelect::From(2)), vec![a2]);
+    assert_eq!(index.find("b", VersionSelect::All), vec![b1]);
+    assert!(index.find("c", VersionSelect::All).is_empty());
+    assert!(index.contains("b", 1));
+    assert!(!index.contains("b", 2));
+  }
+
+  #[test]
+  fn should_catch_up_from_cached_index() {
+    let fixture = FixtureRepository::default();
+    let repo = fixture.repo();
+    let a1 = commit_event(&repo, "a", 1);
+    EventIndex::open(&repo).unwrap();
+    let a2 = commit_event(&repo, "a", 2);
+
+    let cached = EventIndex::load(&repo);
+    assert_eq!(cached.find("a", VersionSelect::All), vec![a1]);
+    let index = EventIndex::open(&repo).unwrap();
+    assert_eq!(index.find("a", VersionSelect::All), vec![a1, a2]);
+  }
+
+  #[test]
+  fn should_rebuild_when_cache_is_missing_or_broken() {
+    let fixture = FixtureRepository::default();
+    let repo = fixture.repo();
+    let a1 = commit_event(&repo, "a", 1);
+    EventIndex::open(&repo).unwrap();
+
+    fs::write(EventIndex::filepath(&repo), "broken").unwrap();
+    let index = EventIndex::open(&repo).unwrap();
+    assert_eq!(index.find("a", VersionSelect::All), vec![a1]);
+
+    fs::remove_file(EventIndex::filepath(&repo)).unwrap();
+    let index = EventIndex::open(&repo).unwrap();
+    assert_eq!(index.find("a", VersionSelect::All), vec![a1]);
+  }
+
+  #[test]
+  fn should_rebuild_when_history_is_rewritten() {
+    let fixture = FixtureRepository::default();
+    let repo = fixture.repo();
+    let a1 = commit_event(&repo, "a", 1);
+    commit_event(&repo, "a", 2);
+    EventIndex::open(&repo).unwrap();
+
+    let target = repo.find_object(a1, None).unwrap();
+    repo.reset(&target, git2::ResetType::Soft, None).unwrap();
+    let index = EventIndex::open(&repo).unwrap();
+    assert_eq!(index.find("a", VersionSelect::All), vec![a1]);
+  }
+}
@@ -1,5 +1,11 @@
+pub use commit_body::*;
+pub use error::*;
 pub use eventstore::*;
+pub use index::*;
 pub use snapshot::*;
 
+mod commit_body;
+mod error;
 mod eventstore;
+mod index;
 mod snapshot;
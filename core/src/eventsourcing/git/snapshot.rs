@@ -14,13 +14,18 @@ pub fn commit_snapshot(repo: &Repository) -> Result<Option<Oid>, crate::git::Err
   if is_head_snapshot || is_working_dir_clean {
     return Ok(None);
   }
+  force_commit_snapshot(repo).map(Some)
+}
 
+/// Commits changes of working dir as `[snapshot]`, even when head is a snapshot already.
+/// Used after snapshot files are regenerated.
+pub fn force_commit_snapshot(repo: &Repository) -> Result<Oid, crate::git::Error> {
   let mut index = repo.index()?;
   index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
+  // stages removed files too.
+  index.update_all(["*"].iter(), None)?;
   index.write()?;
-  let oid = commit(repo, SNAPSHOT_MSG)?;
-
-  Ok(Some(oid))
+  commit(repo, SNAPSHOT_MSG)
 }
 
 #[cfg(test)]
@@ -5,6 +5,7 @@ pub use event::*;
 pub use eventstore::*;
 pub use snapshot::*;
 pub use types::*;
+pub use upcast::*;
 
 mod aggregate;
 mod command;
@@ -15,3 +16,4 @@ mod eventstore;
 pub mod git;
 mod snapshot;
 mod types;
+mod upcast;
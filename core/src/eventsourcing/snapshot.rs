@@ -3,7 +3,7 @@ use async_trait::async_trait;
 use crate::eventsourcing::{Aggregate, AggregateRoot};
 
 #[async_trait]
-pub trait Snapshot<T>
+pub trait Snapshot<T>: Send + Sync
 where
   T: Aggregate,
 {
@@ -12,6 +12,10 @@ where
   async fn load(&self) -> Result<AggregateRoot<T>, Self::Error>;
 
   async fn save(&self, root: &AggregateRoot<T>) -> Result<(), Self::Error>;
+
+  /// Replaces the whole snapshot with `root`, dropping aggregates not in it. The previous
+  /// snapshot must be kept as is when it fails.
+  async fn replace(&self, root: &AggregateRoot<T>) -> Result<(), Self::Error>;
 }
 
 #[cfg(test)]
@@ -0,0 +1,174 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::eventsourcing::{Event, Persisted, Timestamp, Version};
+
+pub type SchemaVersion = u32;
+
+/// Turns raw event of a schema version into the shape of the next version.
+pub type Upcaster = fn(Value) -> Result<Value, serde_json::Error>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum UpcastError {
+  #[error("schema version {0} is not supported")]
+  UnsupportedSchema(SchemaVersion),
+  #[error(transparent)]
+  Json(#[from] serde_json::Error),
+}
+
+/// Persisted event as it is stored, stamped with schema version of the event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredEvent<E> {
+  pub aggregate_id: String,
+  pub version: Version,
+  /// `None` in events stored before occurrence time was recorded.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub occurred_at: Option<Timestamp>,
+  /// Events stored before schema versioning are version 1.
+  #[serde(default = "first_schema")]
+  pub schema: SchemaVersion,
+  pub event: E,
+}
+
+fn first_schema() -> SchemaVersion {
+  1
+}
+
+/// Schema version of events written now, the one after the last upcaster.
+pub fn schema_version<T: Event>() -> SchemaVersion {
+  T::upcasters().len() as SchemaVersion + 1
+}
+
+impl<T: Event> From<Persisted<T>> for StoredEvent<T> {
+  fn from(persisted: Persisted<T>) -> Self {
+    Self {
+      aggregate_id: persisted.aggregate_id,
+      version: persisted.version,
+      occurred_at: Some(persisted.occurred_at),
+      schema: schema_version::<T>(),
+      event: persisted.event,
+    }
+  }
+}
+
+impl StoredEvent<Value> {
+  /// Runs upcasters from the stored schema version, then reads event in the current shape.
+  /// Events without occurrence time are taken as occurred at `stored_at`.
+  pub fn upcast<T>(self, stored_at: Timestamp) -> Result<Persisted<T>, UpcastError>
+  where
+    T: Event + DeserializeOwned,
+  {
+    if self.schema == 0 || self.schema > schema_version::<T>() {
+      return Err(UpcastError::UnsupportedSchema(self.schema));
+    }
+    let mut event = self.event;
+    for upcaster in &T::upcasters()[self.schema as usize - 1..] {
+      event = upcaster(event)?;
+    }
+    Ok(Persisted {
+      aggregate_id: self.aggregate_id,
+      version: self.version,
+      occurred_at: self.occurred_at.unwrap_or(stored_at),
+      event: serde_json::from_value(event)?,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  /// Renamed `text` to `title` on version 2, then added `done` on version 3.
+  #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct NoteEvent {
+    title: String,
+    done: bool,
+  }
+
+  impl Event for NoteEvent {
+    fn name(&self) -> &'static str {
+      "NoteEvent"
+    }
+
+    fn upcasters() -> &'static [Upcaster] {
+      &[
+        |mut x| {
+          x["title"] = x["text"].take();
+          Ok(x)
+        },
+        |mut x| {
+          x["done"] = json!(false);
+          Ok(x)
+        },
+      ]
+    }
+  }
+
+  fn stored(schema: SchemaVersion, event: Value) -> StoredEvent<Value> {
+    StoredEvent {
+      aggregate_id: "note1".to_string(),
+      version: 1,
+      occurred_at: None,
+      schema,
+      event,
+    }
+  }
+
+  #[test]
+  fn upcast_old_events() {
+    let persisted: Persisted<NoteEvent> =
+      stored(1, json!({ "text": "Hello" })).upcast(5_000).unwrap();
+    assert_eq!(persisted.occurred_at, 5_000);
+    assert_eq!(
+      persisted.event,
+      NoteEvent {
+        title: "Hello".to_string(),
+        done: false,
+      }
+    );
+
+    let persisted: Persisted<NoteEvent> = stored(3, json!({ "title": "Hello", "done": true }))
+      .upcast(5_000)
+      .unwrap();
+    assert!(persisted.event.done);
+  }
+
+  #[test]
+  fn stamp_current_schema_version() {
+    let persisted = Persisted {
+      aggregate_id: "note1".to_string(),
+      version: 1,
+      occurred_at: 1_000,
+      event: NoteEvent {
+        title: "Hello".to_string(),
+        done: false,
+      },
+    };
+    let raw = serde_json::to_string(&StoredEvent::from(persisted)).unwrap();
+    assert_eq!(
+      raw,
+      r#"{"aggregateId":"note1","version":1,"occurredAt":1000,"schema":3,"event":{"title":"Hello","done":false}}"#
+    );
+
+    let stored: StoredEvent<Value> =
+      serde_json::from_str(r#"{"aggregateId":"note1","version":1,"event":{"text":"Hi"}}"#).unwrap();
+    assert_eq!(stored.schema, 1);
+  }
+
+  #[test]
+  fn error_on_unknown_schema_or_shape() {
+    let err = stored(4, json!({ "title": "Hello", "done": true }))
+      .upcast::<NoteEvent>(0)
+      .unwrap_err();
+    assert!(matches!(err, UpcastError::UnsupportedSchema(4)));
+
+    let err = stored(3, json!({ "title": "Hello" }))
+      .upcast::<NoteEvent>(0)
+      .unwrap_err();
+    assert!(matches!(err, UpcastError::Json(_)));
+  }
+}
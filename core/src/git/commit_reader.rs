@@ -19,6 +19,7 @@ pub struct CommitReader<'a> {
   revwalk: Revwalk<'a>,
   start_on: CommitReadStartOn,
   end_when: Option<CommitReaderEndWhen>,
+  hidden: Vec<Oid>,
   started: bool,
 }
 
@@ -31,6 +32,7 @@ impl<'a> CommitReader<'a> {
       revwalk,
       start_on: CommitReadStartOn::Head,
       end_when: None,
+      hidden: Vec::new(),
       started: false,
     })
   }
@@ -61,6 +63,13 @@ impl<'a> CommitReader<'a> {
     }
   }
 
+  /// Excludes the commit and its ancestors from reading.
+  #[must_use]
+  pub fn hide_oid(mut self, oid: Oid) -> Self {
+    self.hidden.push(oid);
+    self
+  }
+
   fn push_start(&mut self) -> Result<(), Error> {
     if self.started {
       return Ok(());
@@ -74,6 +83,9 @@ impl<'a> CommitReader<'a> {
         self.revwalk.push(oid)?;
       }
     }
+    for oid in self.hidden.iter() {
+      self.revwalk.hide(*oid)?;
+    }
     self.started = true;
     Ok(())
   }
@@ -83,8 +95,10 @@ impl<'a> Iterator for CommitReader<'a> {
   type Item = Result<CommitInfo, git::Error>;
 
   fn next(&mut self) -> Option<Self::Item> {
-    // returns err when push start fails.
+    // returns err when push start fails, then stops reading.
     if let Err(e) = self.push_start() {
+      self.started = true;
+      let _ = self.revwalk.reset();
       return Some(Err(git::Error::from(e)));
     }
 
@@ -190,4 +204,21 @@ mod tests {
 
     assert_eq!(commits.len(), 0);
   }
+
+  #[test]
+  fn should_not_read_hidden_commits() {
+    let fixture = FixtureRepository::default();
+    commit(&fixture.repo(), "1").unwrap();
+    let oid = commit(&fixture.repo(), "2").unwrap();
+    commit(&fixture.repo(), "3").unwrap();
+    let repo = fixture.repo();
+    let reader = CommitReader::new(&repo)
+      .unwrap()
+      .start_on_head()
+      .hide_oid(oid);
+    let commits: Vec<_> = reader.map(|x| x.unwrap()).collect();
+
+    assert_eq!(commits.len(), 1);
+    assert_eq!(commits[0].message, "3".into());
+  }
 }
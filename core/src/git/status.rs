@@ -51,19 +51,14 @@ pub struct StatusItem {
   pub status: StatusItemType,
 }
 
-#[derive(Copy, Clone, Hash, PartialEq, Debug)]
+#[derive(Copy, Clone, Hash, PartialEq, Debug, Default)]
 pub enum StatusType {
+  #[default]
   WorkingDir,
   Stage,
   Both,
 }
 
-impl Default for StatusType {
-  fn default() -> Self {
-    Self::WorkingDir
-  }
-}
-
 impl From<StatusType> for StatusShow {
   fn from(s: StatusType) -> Self {
     match s {
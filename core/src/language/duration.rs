@@ -0,0 +1,104 @@
+use std::fmt::{Display, Formatter};
+
+use crate::eventsourcing::Timestamp;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DurationUnit {
+  Millisecond,
+  Second,
+  Minute,
+  Hour,
+  Day,
+  Week,
+}
+
+impl DurationUnit {
+  pub fn parse(unit: &str) -> Option<Self> {
+    match unit {
+      "s" | "second" | "seconds" => Some(Self::Second),
+      "m" | "minute" | "minutes" => Some(Self::Minute),
+      "h" | "hour" | "hours" => Some(Self::Hour),
+      "d" | "day" | "days" => Some(Self::Day),
+      "w" | "week" | "weeks" => Some(Self::Week),
+      _ => None,
+    }
+  }
+
+  pub fn as_millis(&self) -> i64 {
+    match self {
+      Self::Millisecond => 1,
+      Self::Second => 1000,
+      Self::Minute => 60 * 1000,
+      Self::Hour => 60 * 60 * 1000,
+      Self::Day => 24 * 60 * 60 * 1000,
+      Self::Week => 7 * 24 * 60 * 60 * 1000,
+    }
+  }
+}
+
+impl Display for DurationUnit {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Self::Millisecond => "ms",
+        Self::Second => "s",
+        Self::Minute => "m",
+        Self::Hour => "h",
+        Self::Day => "d",
+        Self::Week => "w",
+      }
+    )
+  }
+}
+
+/// Duration written in command, such as `30m` or `2 weeks`.
+/// Number without unit is treated as milliseconds, same as `ms` package which web uses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Duration {
+  pub amount: i64,
+  pub unit: DurationUnit,
+}
+
+impl Duration {
+  pub fn new(amount: i64, unit: DurationUnit) -> Self {
+    Self { amount, unit }
+  }
+
+  pub fn as_millis(&self) -> Timestamp {
+    self.amount.saturating_mul(self.unit.as_millis())
+  }
+}
+
+impl Display for Duration {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}{}", self.amount, self.unit)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_units() {
+    assert_eq!(DurationUnit::parse("m"), Some(DurationUnit::Minute));
+    assert_eq!(DurationUnit::parse("weeks"), Some(DurationUnit::Week));
+    assert_eq!(DurationUnit::parse("day"), Some(DurationUnit::Day));
+    assert_eq!(DurationUnit::parse("min"), None);
+  }
+
+  #[test]
+  fn as_millis() {
+    assert_eq!(
+      Duration::new(30, DurationUnit::Minute).as_millis(),
+      1_800_000
+    );
+    assert_eq!(
+      Duration::new(2, DurationUnit::Week).as_millis(),
+      1_209_600_000
+    );
+    assert_eq!(Duration::new(15, DurationUnit::Millisecond).as_millis(), 15);
+  }
+}
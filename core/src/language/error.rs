@@ -0,0 +1,55 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Span {
+  pub from: usize,
+  pub to: usize,
+}
+
+impl Span {
+  pub fn new(from: usize, to: usize) -> Self {
+    Self { from, to }
+  }
+}
+
+impl Display for Span {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}..{}", self.from, self.to)
+  }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+  #[error("unexpected character '{ch}' at {span}")]
+  UnexpectedChar { ch: char, span: Span },
+  #[error("unterminated string at {span}")]
+  UnterminatedString { span: Span },
+  #[error("invalid task id at {span}")]
+  InvalidTaskId { span: Span },
+  #[error("invalid duration at {span}")]
+  InvalidDuration { span: Span },
+  #[error("invalid time at {span}")]
+  InvalidTime { span: Span },
+  #[error("expected {expected}, found '{found}' at {span}")]
+  Unexpected {
+    expected: &'static str,
+    found: String,
+    span: Span,
+  },
+  #[error("expected {expected}, found end of input at {span}")]
+  UnexpectedEnd { expected: &'static str, span: Span },
+}
+
+impl ParseError {
+  pub fn span(&self) -> Span {
+    match self {
+      Self::UnexpectedChar { span, .. } => *span,
+      Self::UnterminatedString { span } => *span,
+      Self::InvalidTaskId { span } => *span,
+      Self::InvalidDuration { span } => *span,
+      Self::InvalidTime { span } => *span,
+      Self::Unexpected { span, .. } => *span,
+      Self::UnexpectedEnd { span, .. } => *span,
+    }
+  }
+}
@@ -0,0 +1,242 @@
+use crate::language::{Duration, DurationUnit, ParseError, Span};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+  Word(String),
+  String(String),
+  TaskId(i32),
+  Number(i64),
+  Duration(Duration),
+  /// Time of day such as `09:00`.
+  Time {
+    hour: u32,
+    minute: u32,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+  pub kind: TokenKind,
+  pub span: Span,
+}
+
+pub fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+  let mut lexer = Lexer { text, pos: 0 };
+  let mut tokens = Vec::new();
+  while let Some(token) = lexer.next_token()? {
+    tokens.push(token);
+  }
+  Ok(tokens)
+}
+
+struct Lexer<'a> {
+  text: &'a str,
+  pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+  fn peek(&self) -> Option<char> {
+    self.text[self.pos..].chars().next()
+  }
+
+  fn bump(&mut self) -> Option<char> {
+    let ch = self.peek()?;
+    self.pos += ch.len_utf8();
+    Some(ch)
+  }
+
+  fn eat_while<F>(&mut self, predicate: F) -> &'a str
+  where
+    F: Fn(char) -> bool,
+  {
+    let from = self.pos;
+    while let Some(ch) = self.peek() {
+      if !predicate(ch) {
+        break;
+      }
+      self.pos += ch.len_utf8();
+    }
+    &self.text[from..self.pos]
+  }
+
+  fn next_token(&mut self) -> Result<Option<Token>, ParseError> {
+    self.eat_while(char::is_whitespace);
+    let from = self.pos;
+    let ch = match self.peek() {
+      Some(ch) => ch,
+      None => return Ok(None),
+    };
+
+    let kind = match ch {
+      '"' => {
+        self.bump();
+        let value = self.eat_while(|x| x != '"').to_string();
+        if self.bump().is_none() {
+          return Err(ParseError::UnterminatedString {
+            span: Span::new(from, self.pos),
+          });
+        }
+        TokenKind::String(value)
+      }
+      '#' => {
+        self.bump();
+        let digits = self.eat_while(|x| x.is_ascii_digit());
+        let no = digits
+          .parse::<i32>()
+          .map_err(|_| ParseError::InvalidTaskId {
+            span: Span::new(from, self.pos),
+          })?;
+        TokenKind::TaskId(no)
+      }
+      '0'..='9' => {
+        let digits = self.eat_while(|x| x.is_ascii_digit());
+        if self.peek() == Some(':') {
+          self.bump();
+          let minutes = self.eat_while(|x| x.is_ascii_digit());
+          let span = Span::new(from, self.pos);
+          return match (digits.parse::<u32>(), minutes.parse::<u32>()) {
+            (Ok(hour), Ok(minute)) if hour < 24 && minute < 60 && minutes.len() == 2 => {
+              Ok(Some(Token {
+                kind: TokenKind::Time { hour, minute },
+                span,
+              }))
+            }
+            _ => Err(ParseError::InvalidTime { span }),
+          };
+        }
+        let amount = match digits.starts_with('0') {
+          true => None,
+          false => digits.parse::<i64>().ok(),
+        };
+        let unit = self.eat_while(is_word_char);
+        let span = Span::new(from, self.pos);
+        let amount = amount.ok_or(ParseError::InvalidDuration { span })?;
+        match unit.is_empty() {
+          true => TokenKind::Number(amount),
+          false => {
+            let unit = DurationUnit::parse(unit).ok_or(ParseError::InvalidDuration { span })?;
+            TokenKind::Duration(Duration::new(amount, unit))
+          }
+        }
+      }
+      x if is_word_char(x) => TokenKind::Word(self.eat_while(is_word_char).to_string()),
+      x => {
+        self.bump();
+        return Err(ParseError::UnexpectedChar {
+          ch: x,
+          span: Span::new(from, self.pos),
+        });
+      }
+    };
+
+    Ok(Some(Token {
+      kind,
+      span: Span::new(from, self.pos),
+    }))
+  }
+}
+
+fn is_word_char(ch: char) -> bool {
+  ch.is_ascii_alphabetic() || ch == '_'
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn kinds(text: &str) -> Vec<TokenKind> {
+    tokenize(text)
+      .unwrap()
+      .into_iter()
+      .map(|x| x.kind)
+      .collect()
+  }
+
+  #[test]
+  fn tokenize_command() {
+    assert_eq!(
+      kinds(r#"set #12 title "hello world""#),
+      vec![
+        TokenKind::Word("set".to_string()),
+        TokenKind::TaskId(12),
+        TokenKind::Word("title".to_string()),
+        TokenKind::String("hello world".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn tokenize_durations() {
+    assert_eq!(
+      kinds("30m 2 weeks 10"),
+      vec![
+        TokenKind::Duration(Duration::new(30, DurationUnit::Minute)),
+        TokenKind::Number(2),
+        TokenKind::Word("weeks".to_string()),
+        TokenKind::Number(10),
+      ]
+    );
+  }
+
+  #[test]
+  fn tokenize_times() {
+    assert_eq!(
+      kinds("at 09:00 9:30"),
+      vec![
+        TokenKind::Word("at".to_string()),
+        TokenKind::Time { hour: 9, minute: 0 },
+        TokenKind::Time {
+          hour: 9,
+          minute: 30
+        },
+      ]
+    );
+    for text in ["24:00", "9:60", "9:5", "9:"] {
+      assert!(matches!(
+        tokenize(text).unwrap_err(),
+        ParseError::InvalidTime { .. }
+      ));
+    }
+  }
+
+  #[test]
+  fn tokens_have_spans() {
+    let tokens = tokenize(r#"new  "a""#).unwrap();
+    assert_eq!(tokens[0].span, Span::new(0, 3));
+    assert_eq!(tokens[1].span, Span::new(5, 8));
+  }
+
+  #[test]
+  fn error_on_unterminated_string() {
+    let err = tokenize(r#"new "title"#).unwrap_err();
+    assert_eq!(
+      err,
+      ParseError::UnterminatedString {
+        span: Span::new(4, 10)
+      }
+    );
+  }
+
+  #[test]
+  fn error_on_invalid_duration() {
+    assert!(matches!(
+      tokenize("30x").unwrap_err(),
+      ParseError::InvalidDuration { .. }
+    ));
+    assert!(matches!(
+      tokenize("05m").unwrap_err(),
+      ParseError::InvalidDuration { .. }
+    ));
+  }
+
+  #[test]
+  fn error_on_unexpected_char() {
+    assert_eq!(
+      tokenize("new !").unwrap_err(),
+      ParseError::UnexpectedChar {
+        ch: '!',
+        span: Span::new(4, 5)
+      }
+    );
+  }
+}
@@ -0,0 +1,9 @@
+pub use duration::*;
+pub use error::*;
+pub use lexer::*;
+pub use parser::*;
+
+mod duration;
+mod error;
+mod lexer;
+mod parser;
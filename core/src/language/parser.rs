@@ -0,0 +1,665 @@
+use chrono::FixedOffset;
+
+use crate::domain::recurrence::{Recurrence, Weekday};
+use crate::domain::task::{TaskCommand, TaskId, TaskSchedule, TaskStatus};
+use crate::eventsourcing::Timestamp;
+use crate::language::{tokenize, Duration, DurationUnit, ParseError, Span, Token, TokenKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithStatus {
+  pub status: TaskStatus,
+  pub duration: Option<Duration>,
+  pub repeat: Option<Recurrence>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetClause {
+  Title(String),
+  Status(WithStatus),
+}
+
+/// Syntax tree of command line, mirrors `language/src/geeks-tracker.grammar`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+  New {
+    title: String,
+    with_status: Option<WithStatus>,
+  },
+  Set {
+    id: TaskId,
+    clause: SetClause,
+  },
+  Delete {
+    id: TaskId,
+  },
+}
+
+impl Statement {
+  /// Converts into task command. Duration is resolved as schedule relative to `now` (millis),
+  /// and recurring schedule starts at its first occurrence in `offset` unless duration is given.
+  pub fn into_task_command(self, now: Timestamp, offset: FixedOffset) -> TaskCommand {
+    let schedule = |with_status: &WithStatus| {
+      let at = match (&with_status.duration, &with_status.repeat) {
+        (Some(duration), _) => now + duration.as_millis(),
+        (None, Some(repeat)) => repeat.next(now, now, &offset)?,
+        (None, None) => return None,
+      };
+      Some(TaskSchedule {
+        at,
+        status: with_status.status.to_owned(),
+        repeat: with_status.repeat.to_owned(),
+      })
+    };
+
+    match self {
+      Self::New { title, with_status } => match with_status {
+        Some(with_status) => match schedule(&with_status) {
+          Some(schedule) => TaskCommand::Create {
+            title,
+            status: None,
+            schedule: Some(schedule),
+          },
+          None => TaskCommand::Create {
+            title,
+            status: Some(with_status.status),
+            schedule: None,
+          },
+        },
+        None => TaskCommand::Create {
+          title,
+          status: None,
+          schedule: None,
+        },
+      },
+      Self::Set { id, clause } => match clause {
+        SetClause::Title(title) => TaskCommand::UpdateTitle { id, title },
+        SetClause::Status(with_status) => match schedule(&with_status) {
+          Some(schedule) => TaskCommand::UpdateSchedule {
+            id,
+            schedule: Some(schedule),
+          },
+          None => TaskCommand::UpdateStatus {
+            id,
+            status: with_status.status,
+            force: false,
+          },
+        },
+      },
+      Self::Delete { id } => TaskCommand::Delete { id },
+    }
+  }
+}
+
+pub fn parse(text: &str) -> Result<Statement, ParseError> {
+  let tokens = tokenize(text)?;
+  let mut parser = Parser {
+    tokens,
+    pos: 0,
+    end: text.len(),
+  };
+  let statement = parser.statement()?;
+  parser.finish()?;
+  Ok(statement)
+}
+
+/// Parses standalone duration such as `30m` or `2 weeks`.
+pub fn parse_duration(text: &str) -> Result<Duration, ParseError> {
+  let tokens = tokenize(text)?;
+  let mut parser = Parser {
+    tokens,
+    pos: 0,
+    end: text.len(),
+  };
+  let duration = match parser.duration()? {
+    Some(duration) => duration,
+    None => {
+      const EXPECTED: &str = "duration";
+      let token = parser.next(EXPECTED)?;
+      return Err(parser.unexpected(EXPECTED, &token));
+    }
+  };
+  parser.finish()?;
+  Ok(duration)
+}
+
+pub fn parse_task_command(
+  text: &str,
+  now: Timestamp,
+  offset: FixedOffset,
+) -> Result<TaskCommand, ParseError> {
+  parse(text).map(|x| x.into_task_command(now, offset))
+}
+
+struct Parser {
+  tokens: Vec<Token>,
+  pos: usize,
+  end: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos)
+  }
+
+  fn next(&mut self, expected: &'static str) -> Result<Token, ParseError> {
+    match self.tokens.get(self.pos).cloned() {
+      Some(token) => {
+        self.pos += 1;
+        Ok(token)
+      }
+      None => Err(ParseError::UnexpectedEnd {
+        expected,
+        span: Span::new(self.end, self.end),
+      }),
+    }
+  }
+
+  fn unexpected(&self, expected: &'static str, token: &Token) -> ParseError {
+    ParseError::Unexpected {
+      expected,
+      found: describe(&token.kind),
+      span: token.span,
+    }
+  }
+
+  fn keyword(&mut self, keyword: &'static str) -> Result<(), ParseError> {
+    let token = self.next(keyword)?;
+    match &token.kind {
+      TokenKind::Word(x) if x == keyword => Ok(()),
+      _ => Err(self.unexpected(keyword, &token)),
+    }
+  }
+
+  fn peek_keyword(&self, keyword: &str) -> bool {
+    matches!(self.peek(), Some(Token { kind: TokenKind::Word(x), .. }) if x == keyword)
+  }
+
+  fn statement(&mut self) -> Result<Statement, ParseError> {
+    const EXPECTED: &str = "'new', 'set' or 'delete'";
+    let token = self.next(EXPECTED)?;
+    match &token.kind {
+      TokenKind::Word(x) if x == "new" => {
+        let title = self.string()?;
+        let with_status = match self.peek_keyword("status") {
+          true => Some(self.with_status()?),
+          false => None,
+        };
+        Ok(Statement::New { title, with_status })
+      }
+      TokenKind::Word(x) if x == "set" => {
+        let id = self.task_id()?;
+        let clause = match self.peek_keyword("title") {
+          true => {
+            self.keyword("title")?;
+            SetClause::Title(self.string()?)
+          }
+          false => match self.peek_keyword("status") {
+            true => SetClause::Status(self.with_status()?),
+            false => {
+              const EXPECTED: &str = "'title' or 'status'";
+              let token = self.next(EXPECTED)?;
+              return Err(self.unexpected(EXPECTED, &token));
+            }
+          },
+        };
+        Ok(Statement::Set { id, clause })
+      }
+      TokenKind::Word(x) if x == "delete" => {
+        let id = self.task_id()?;
+        Ok(Statement::Delete { id })
+      }
+      _ => Err(self.unexpected(EXPECTED, &token)),
+    }
+  }
+
+  fn string(&mut self) -> Result<String, ParseError> {
+    let token = self.next("string")?;
+    match token.kind {
+      TokenKind::String(x) => Ok(x),
+      _ => Err(self.unexpected("string", &token)),
+    }
+  }
+
+  fn task_id(&mut self) -> Result<TaskId, ParseError> {
+    let token = self.next("task id")?;
+    match token.kind {
+      TokenKind::TaskId(no) => Ok(TaskId::new(no)),
+      _ => Err(self.unexpected("task id", &token)),
+    }
+  }
+
+  fn with_status(&mut self) -> Result<WithStatus, ParseError> {
+    const EXPECTED: &str = "task status";
+    self.keyword("status")?;
+    let token = self.next(EXPECTED)?;
+    let status = match &token.kind {
+      TokenKind::Word(x) => x.parse::<TaskStatus>().ok(),
+      _ => None,
+    }
+    .ok_or_else(|| self.unexpected(EXPECTED, &token))?;
+    let duration = self.duration()?;
+    let repeat = self.repeat()?;
+    Ok(WithStatus {
+      status,
+      duration,
+      repeat,
+    })
+  }
+
+  fn repeat(&mut self) -> Result<Option<Recurrence>, ParseError> {
+    const EXPECTED: &str = "duration, 'day', 'weekday' or day of week";
+    if !self.peek_keyword("every") {
+      return Ok(None);
+    }
+    self.keyword("every")?;
+    if let Some(duration) = self.duration()? {
+      return Ok(Some(Recurrence::Interval {
+        every: duration.as_millis(),
+      }));
+    }
+
+    let token = self.next(EXPECTED)?;
+    let weekdays = match &token.kind {
+      TokenKind::Word(x) if x == "day" => Vec::new(),
+      TokenKind::Word(x) if x == "weekday" => Weekday::WEEKDAYS.to_vec(),
+      TokenKind::Word(x) => {
+        let first = x
+          .parse::<Weekday>()
+          .map_err(|_| self.unexpected(EXPECTED, &token))?;
+        let mut weekdays = vec![first];
+        while let Some(weekday) = self.peek_weekday() {
+          self.pos += 1;
+          if !weekdays.contains(&weekday) {
+            weekdays.push(weekday);
+          }
+        }
+        weekdays
+      }
+      _ => return Err(self.unexpected(EXPECTED, &token)),
+    };
+
+    let (hour, minute) = match self.peek_keyword("at") {
+      true => {
+        self.keyword("at")?;
+        self.time()?
+      }
+      false => (0, 0),
+    };
+    Ok(Some(Recurrence::Weekly {
+      weekdays,
+      hour,
+      minute,
+    }))
+  }
+
+  fn peek_weekday(&self) -> Option<Weekday> {
+    match self.peek() {
+      Some(Token {
+        kind: TokenKind::Word(x),
+        ..
+      }) => x.parse().ok(),
+      _ => None,
+    }
+  }
+
+  fn time(&mut self) -> Result<(u32, u32), ParseError> {
+    let token = self.next("time")?;
+    match token.kind {
+      TokenKind::Time { hour, minute } => Ok((hour, minute)),
+      _ => Err(self.unexpected("time", &token)),
+    }
+  }
+
+  fn duration(&mut self) -> Result<Option<Duration>, ParseError> {
+    let token = match self.peek() {
+      Some(token) => token.clone(),
+      None => return Ok(None),
+    };
+    match token.kind {
+      TokenKind::Duration(duration) => {
+        self.pos += 1;
+        Ok(Some(duration))
+      }
+      TokenKind::Number(amount) => {
+        self.pos += 1;
+        // allows space between amount and unit. (e.g. "2 weeks")
+        let unit = match self.peek() {
+          Some(Token {
+            kind: TokenKind::Word(x),
+            ..
+          }) => DurationUnit::parse(x),
+          _ => None,
+        };
+        match unit {
+          Some(unit) => {
+            self.pos += 1;
+            Ok(Some(Duration::new(amount, unit)))
+          }
+          None => Ok(Some(Duration::new(amount, DurationUnit::Millisecond))),
+        }
+      }
+      _ => Ok(None),
+    }
+  }
+
+  fn finish(&mut self) -> Result<(), ParseError> {
+    match self.peek() {
+      Some(token) => Err(self.unexpected("end of input", token)),
+      None => Ok(()),
+    }
+  }
+}
+
+fn describe(kind: &TokenKind) -> String {
+  match kind {
+    TokenKind::Word(x) => x.to_owned(),
+    TokenKind::String(x) => format!("\"{}\"", x),
+    TokenKind::TaskId(x) => format!("#{}", x),
+    TokenKind::Number(x) => x.to_string(),
+    TokenKind::Duration(x) => x.to_string(),
+    TokenKind::Time { hour, minute } => format!("{}:{:02}", hour, minute),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // cases from `language/src/__tests__/commands.txt`
+  #[test]
+  fn new_task() {
+    assert_eq!(
+      parse(r#"new "title""#).unwrap(),
+      Statement::New {
+        title: "title".to_string(),
+        with_status: None,
+      }
+    );
+  }
+
+  #[test]
+  fn new_task_with_status() {
+    assert_eq!(
+      parse(r#"new "title" status done"#).unwrap(),
+      Statement::New {
+        title: "title".to_string(),
+        with_status: Some(WithStatus {
+          status: TaskStatus::Done,
+          duration: None,
+          repeat: None,
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn new_task_with_status_and_duration() {
+    assert_eq!(
+      parse(r#"new "title" status done 30m"#).unwrap(),
+      Statement::New {
+        title: "title".to_string(),
+        with_status: Some(WithStatus {
+          status: TaskStatus::Done,
+          duration: Some(Duration::new(30, DurationUnit::Minute)),
+          repeat: None,
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn set_task_title() {
+    assert_eq!(
+      parse(r#"set #123 title "new title""#).unwrap(),
+      Statement::Set {
+        id: TaskId::new(123),
+        clause: SetClause::Title("new title".to_string()),
+      }
+    );
+  }
+
+  #[test]
+  fn set_task_status() {
+    assert_eq!(
+      parse("set #123 status queue").unwrap(),
+      Statement::Set {
+        id: TaskId::new(123),
+        clause: SetClause::Status(WithStatus {
+          status: TaskStatus::Queue,
+          duration: None,
+          repeat: None,
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn set_task_status_with_duration() {
+    assert_eq!(
+      parse("set #123 status queue 30m").unwrap(),
+      Statement::Set {
+        id: TaskId::new(123),
+        clause: SetClause::Status(WithStatus {
+          status: TaskStatus::Queue,
+          duration: Some(Duration::new(30, DurationUnit::Minute)),
+          repeat: None,
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn delete_task() {
+    assert_eq!(
+      parse("delete #3").unwrap(),
+      Statement::Delete { id: TaskId::new(3) }
+    );
+  }
+
+  #[test]
+  fn duration_with_space_and_long_unit() {
+    let statement = parse("set #1 status in_progress 2 weeks").unwrap();
+    assert_eq!(
+      statement,
+      Statement::Set {
+        id: TaskId::new(1),
+        clause: SetClause::Status(WithStatus {
+          status: TaskStatus::InProgress,
+          duration: Some(Duration::new(2, DurationUnit::Week)),
+          repeat: None,
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn into_task_commands() {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    assert_eq!(
+      parse_task_command(r#"new "title" status queue"#, 0, utc).unwrap(),
+      TaskCommand::Create {
+        title: "title".to_string(),
+        status: Some(TaskStatus::Queue),
+        schedule: None,
+      }
+    );
+    assert_eq!(
+      parse_task_command(r#"new "title" status done 1s"#, 1000, utc).unwrap(),
+      TaskCommand::Create {
+        title: "title".to_string(),
+        status: None,
+        schedule: Some(TaskSchedule {
+          at: 2000,
+          status: TaskStatus::Done,
+          repeat: None,
+        }),
+      }
+    );
+    assert_eq!(
+      parse_task_command("set #2 status done 1m", 0, utc).unwrap(),
+      TaskCommand::UpdateSchedule {
+        id: TaskId::new(2),
+        schedule: Some(TaskSchedule {
+          at: 60_000,
+          status: TaskStatus::Done,
+          repeat: None,
+        }),
+      }
+    );
+    assert_eq!(
+      parse_task_command("set #2 status done", 0, utc).unwrap(),
+      TaskCommand::UpdateStatus {
+        id: TaskId::new(2),
+        status: TaskStatus::Done,
+        force: false,
+      }
+    );
+    assert_eq!(
+      parse_task_command("delete #2", 0, utc).unwrap(),
+      TaskCommand::Delete { id: TaskId::new(2) }
+    );
+  }
+
+  #[test]
+  fn repeat_schedules() {
+    let parse_repeat = |text: &str| match parse(text).unwrap() {
+      Statement::Set {
+        clause: SetClause::Status(x),
+        ..
+      } => x.repeat,
+      _ => None,
+    };
+    assert_eq!(
+      parse_repeat("set #1 status queue every 2h"),
+      Some(Recurrence::Interval {
+        every: 2 * 60 * 60 * 1000
+      })
+    );
+    assert_eq!(
+      parse_repeat("set #1 status queue every 3 hours"),
+      Some(Recurrence::Interval {
+        every: 3 * 60 * 60 * 1000
+      })
+    );
+    assert_eq!(
+      parse_repeat("set #1 status in_progress every weekday at 09:00"),
+      Some(Recurrence::Weekly {
+        weekdays: Weekday::WEEKDAYS.to_vec(),
+        hour: 9,
+        minute: 0,
+      })
+    );
+    assert_eq!(
+      parse_repeat("set #1 status queue every monday thu"),
+      Some(Recurrence::Weekly {
+        weekdays: vec![Weekday::Monday, Weekday::Thursday],
+        hour: 0,
+        minute: 0,
+      })
+    );
+    assert_eq!(
+      parse_repeat("set #1 status queue every day at 18:30"),
+      Some(Recurrence::Weekly {
+        weekdays: Vec::new(),
+        hour: 18,
+        minute: 30,
+      })
+    );
+    assert!(parse("set #1 status queue every").is_err());
+    assert!(parse("set #1 status queue every title").is_err());
+    assert!(parse("set #1 status queue every day at").is_err());
+  }
+
+  #[test]
+  fn repeat_schedule_into_task_command() {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    let hour = 60 * 60 * 1000;
+    let every_day = Recurrence::Weekly {
+      weekdays: Vec::new(),
+      hour: 9,
+      minute: 0,
+    };
+    assert_eq!(
+      parse_task_command("set #2 status queue every day at 09:00", 10 * hour, utc).unwrap(),
+      TaskCommand::UpdateSchedule {
+        id: TaskId::new(2),
+        schedule: Some(TaskSchedule {
+          at: 33 * hour,
+          status: TaskStatus::Queue,
+          repeat: Some(every_day.clone()),
+        }),
+      }
+    );
+    assert_eq!(
+      parse_task_command("set #2 status queue 1h every day at 09:00", 0, utc).unwrap(),
+      TaskCommand::UpdateSchedule {
+        id: TaskId::new(2),
+        schedule: Some(TaskSchedule {
+          at: hour,
+          status: TaskStatus::Queue,
+          repeat: Some(every_day),
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn standalone_duration() {
+    assert_eq!(
+      parse_duration("2 hours").unwrap(),
+      Duration::new(2, DurationUnit::Hour)
+    );
+    assert!(parse_duration("hours").is_err());
+    assert!(parse_duration("2h 3m").is_err());
+  }
+
+  #[test]
+  fn error_on_unknown_command() {
+    assert_eq!(
+      parse("add #1").unwrap_err(),
+      ParseError::Unexpected {
+        expected: "'new', 'set' or 'delete'",
+        found: "add".to_string(),
+        span: Span::new(0, 3),
+      }
+    );
+  }
+
+  #[test]
+  fn error_on_unknown_status() {
+    assert_eq!(
+      parse("set #1 status doing").unwrap_err(),
+      ParseError::Unexpected {
+        expected: "task status",
+        found: "doing".to_string(),
+        span: Span::new(14, 19),
+      }
+    );
+  }
+
+  #[test]
+  fn error_on_missing_title() {
+    assert_eq!(
+      parse("new").unwrap_err(),
+      ParseError::UnexpectedEnd {
+        expected: "string",
+        span: Span::new(3, 3),
+      }
+    );
+    assert_eq!(
+      parse("set #1").unwrap_err(),
+      ParseError::UnexpectedEnd {
+        expected: "'title' or 'status'",
+        span: Span::new(6, 6),
+      }
+    );
+  }
+
+  #[test]
+  fn error_on_trailing_tokens() {
+    assert_eq!(
+      parse(r#"delete #1 "foo""#).unwrap_err().span(),
+      Span::new(10, 15)
+    );
+  }
+}
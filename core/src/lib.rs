@@ -2,3 +2,10 @@ pub mod dispatch;
 pub mod domain;
 pub mod eventsourcing;
 pub mod git;
+pub mod language;
+pub mod report;
+pub mod search;
+pub mod snapshots;
+pub mod sync;
+pub mod utils;
+pub mod workspace;
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use chrono::{Duration, FixedOffset, NaiveDate, TimeZone};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::domain::task::{TaskEvent, TaskId, TaskStatus};
+use crate::eventsourcing::git::GitEventstore;
+use crate::eventsourcing::{Persisted, Timestamp};
+use crate::git::CommitReader;
+use crate::report::Error;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[typeshare]
+pub enum ReportFormat {
+  Markdown,
+  Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct ReportedTask {
+  pub id: String,
+  pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct DailyReport {
+  /// `YYYY-MM-DD` in the offset the report was built with.
+  pub date: String,
+  pub created: Vec<ReportedTask>,
+  pub started: Vec<ReportedTask>,
+  pub completed: Vec<ReportedTask>,
+  /// Tasks still in progress at the end of the day.
+  pub in_progress: Vec<ReportedTask>,
+}
+
+/// Summary of task activity between `from` (inclusive) and `to` (exclusive), grouped by day.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct Report {
+  pub from: Timestamp,
+  pub to: Timestamp,
+  pub days: Vec<DailyReport>,
+}
+
+impl Report {
+  /// Reads `[event]` commits of the repository. Commits are not in occurrence order when
+  /// history is rewritten on sync, so events are sorted by their occurrence time.
+  pub fn read(
+    repo: &Repository,
+    from: Timestamp,
+    to: Timestamp,
+    offset: FixedOffset,
+  ) -> Result<Self, Error> {
+    let mut commits = Vec::new();
+    for commit in CommitReader::new(repo)?.start_on_head() {
+      commits.push(GitEventstore::<TaskEvent>::commit_to_events(commit?)?);
+    }
+    let mut events: Vec<_> = commits
+      .into_iter()
+      .rev()
+      .flatten()
+      .filter(|x| x.occurred_at < to)
+      .collect();
+    events.sort_by_key(|x| x.occurred_at);
+    Ok(Self::build(events, from, to, offset))
+  }
+
+  /// Builds report from events in occurrence order. Events before `from` are only used to know
+  /// titles and statuses of tasks.
+  pub fn build(
+    events: Vec<Persisted<TaskEvent>>,
+    from: Timestamp,
+    to: Timestamp,
+    offset: FixedOffset,
+  ) -> Self {
+    let mut tasks: HashMap<String, ReportedState> = HashMap::new();
+    let mut events = events.into_iter().peekable();
+    let mut days = Vec::new();
+
+    for date in dates_between(from, to, &offset) {
+      let mut day = DailyReport {
+        date: date.format("%Y-%m-%d").to_string(),
+        created: Vec::new(),
+        started: Vec::new(),
+        completed: Vec::new(),
+        in_progress: Vec::new(),
+      };
+      let day_end = start_of_day(date + Duration::days(1), &offset).min(to);
+
+      while let Some(persisted) = events.next_if(|x| x.occurred_at < day_end) {
+        let id = persisted.aggregate_id;
+        let activity = apply(&mut tasks, &id, persisted.event);
+        if persisted.occurred_at < from {
+          continue;
+        }
+        let Some(state) = tasks.get(&id) else {
+          continue;
+        };
+        let reported = ReportedTask {
+          id: state.id.to_string(),
+          title: state.title.to_owned(),
+        };
+        if activity.created {
+          day.created.push(reported.clone());
+        }
+        if activity.started {
+          day.started.push(reported.clone());
+        }
+        if activity.completed {
+          day.completed.push(reported);
+        }
+      }
+
+      let mut in_progress: Vec<_> = tasks
+        .iter()
+        .filter(|(_, x)| x.status == TaskStatus::InProgress)
+        .map(|(_, x)| ReportedTask {
+          id: x.id.to_string(),
+          title: x.title.to_owned(),
+        })
+        .collect();
+      in_progress.sort_by(|a, b| (a.id.len(), &a.id).cmp(&(b.id.len(), &b.id)));
+      day.in_progress = in_progress;
+      days.push(day);
+    }
+
+    Self { from, to, days }
+  }
+
+  pub fn render(&self, format: ReportFormat) -> Result<String, Error> {
+    match format {
+      ReportFormat::Markdown => Ok(self.to_markdown()),
+      ReportFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+    }
+  }
+
+  pub fn to_markdown(&self) -> String {
+    let mut out = String::new();
+    let title = match (self.days.first(), self.days.last()) {
+      (Some(first), Some(last)) if first.date != last.date => {
+        format!("{} ~ {}", first.date, last.date)
+      }
+      (Some(first), _) => first.date.to_owned(),
+      _ => "empty".to_string(),
+    };
+    let _ = writeln!(out, "# Report {}", title);
+
+    for day in &self.days {
+      let _ = write!(out, "\n## {}\n", day.date);
+      let sections = [
+        ("Created", &day.created),
+        ("Started", &day.started),
+        ("Completed", &day.completed),
+        ("In progress", &day.in_progress),
+      ];
+      if sections.iter().all(|(_, tasks)| tasks.is_empty()) {
+        let _ = write!(out, "\nNo activity\n");
+        continue;
+      }
+      for (name, tasks) in sections {
+        if tasks.is_empty() {
+          continue;
+        }
+        let _ = write!(out, "\n### {}\n\n", name);
+        for task in tasks {
+          let _ = writeln!(out, "- {} {}", task.id, task.title);
+        }
+      }
+    }
+    out
+  }
+}
+
+struct ReportedState {
+  /// Number shown to users, events are keyed by aggregate id which may differ.
+  id: TaskId,
+  title: String,
+  status: TaskStatus,
+}
+
+#[derive(Default)]
+struct Activity {
+  created: bool,
+  started: bool,
+  completed: bool,
+}
+
+fn apply(tasks: &mut HashMap<String, ReportedState>, id: &str, event: TaskEvent) -> Activity {
+  let mut activity = Activity::default();
+  match event {
+    TaskEvent::Created {
+      id: no,
+      title,
+      status,
+      ..
+    } => {
+      activity.created = true;
+      activity.started = status == TaskStatus::InProgress;
+      activity.completed = status == TaskStatus::Done;
+      tasks.insert(
+        id.to_owned(),
+        ReportedState {
+          id: no,
+          title,
+          status,
+        },
+      );
+    }
+    TaskEvent::Restored { task } => {
+      tasks.insert(
+        id.to_owned(),
+        ReportedState {
+          id: task.id,
+          title: task.title,
+          status: task.status,
+        },
+      );
+    }
+    TaskEvent::TitleUpdated { title } => {
+      if let Some(state) = tasks.get_mut(id) {
+        state.title = title;
+      }
+    }
+    TaskEvent::StatusUpdated { status, .. } | TaskEvent::ScheduleFired { status, .. } => {
+      if let Some(state) = tasks.get_mut(id) {
+        activity.started = status == TaskStatus::InProgress && state.status != status;
+        activity.completed = status == TaskStatus::Done && state.status != status;
+        state.status = status;
+      }
+    }
+    TaskEvent::Deleted {} => {
+      tasks.remove(id);
+    }
+    _ => {}
+  }
+  activity
+}
+
+fn start_of_day(date: NaiveDate, offset: &FixedOffset) -> Timestamp {
+  let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+  offset
+    .from_local_datetime(&midnight)
+    .single()
+    .map(|x| x.timestamp_millis())
+    .unwrap_or_default()
+}
+
+fn dates_between(from: Timestamp, to: Timestamp, offset: &FixedOffset) -> Vec<NaiveDate> {
+  if to <= from {
+    return Vec::new();
+  }
+  let (Some(first), Some(last)) = (
+    offset.timestamp_millis_opt(from).single(),
+    offset.timestamp_millis_opt(to - 1).single(),
+  ) else {
+    return Vec::new();
+  };
+  first
+    .date_naive()
+    .iter_days()
+    .take_while(|x| *x <= last.date_naive())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::Utc;
+  use geeks_tracker_testing::git::FixtureRepository;
+
+  use crate::domain::task::TaskId;
+  use crate::eventsourcing::Eventstore;
+
+  use super::*;
+
+  const DAY: Timestamp = 24 * 60 * 60 * 1000;
+
+  fn persisted(id: i32, version: u64, event: TaskEvent) -> Persisted<TaskEvent> {
+    Persisted {
+      aggregate_id: TaskId::new(id).to_string(),
+      version,
+      occurred_at: 0,
+      event,
+    }
+  }
+
+  fn created(id: i32, title: &str) -> Persisted<TaskEvent> {
+    persisted(
+      id,
+      1,
+      TaskEvent::Created {
+        uid: None,
+        id: TaskId::new(id),
+        title: title.to_string(),
+        body: None,
+        status: TaskStatus::Backlog,
+        schedule: None,
+      },
+    )
+  }
+
+  fn status(id: i32, version: u64, status: TaskStatus) -> Persisted<TaskEvent> {
+    persisted(id, version, TaskEvent::StatusUpdated { status })
+  }
+
+  fn at(occurred_at: Timestamp, persisted: Persisted<TaskEvent>) -> Persisted<TaskEvent> {
+    Persisted {
+      occurred_at,
+      ..persisted
+    }
+  }
+
+  fn task(id: &str, title: &str) -> ReportedTask {
+    ReportedTask {
+      id: id.to_string(),
+      title: title.to_string(),
+    }
+  }
+
+  #[test]
+  fn group_activities_by_day() {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    let events = vec![
+      at(0, created(1, "Old task")),
+      at(10, status(1, 2, TaskStatus::InProgress)),
+      at(DAY + 10, created(2, "Write docs")),
+      at(DAY + 20, status(2, 2, TaskStatus::InProgress)),
+      at(2 * DAY + 10, status(1, 3, TaskStatus::Done)),
+      at(3 * DAY + 10, created(3, "Too late")),
+    ];
+
+    let report = Report::build(events, DAY, 3 * DAY, utc);
+
+    assert_eq!(report.days.len(), 2);
+    assert_eq!(report.days[0].date, "1970-01-02");
+    assert_eq!(report.days[0].created, vec![task("#2", "Write docs")]);
+    assert_eq!(report.days[0].started, vec![task("#2", "Write docs")]);
+    assert_eq!(
+      report.days[0].in_progress,
+      vec![task("#1", "Old task"), task("#2", "Write docs")]
+    );
+    assert_eq!(report.days[1].date, "1970-01-03");
+    assert!(report.days[1].created.is_empty());
+    assert_eq!(report.days[1].completed, vec![task("#1", "Old task")]);
+    assert_eq!(report.days[1].in_progress, vec![task("#2", "Write docs")]);
+  }
+
+  #[test]
+  fn group_by_day_in_offset() {
+    let kst = FixedOffset::east_opt(9 * 60 * 60).unwrap();
+    let hour = 60 * 60 * 1000;
+    // 1970-01-01T20:00 UTC is 1970-01-02T05:00 KST.
+    let events = vec![at(20 * hour, created(1, "Write docs"))];
+
+    let report = Report::build(events, 0, DAY, kst);
+
+    assert_eq!(report.days.len(), 2);
+    assert!(report.days[0].created.is_empty());
+    assert_eq!(report.days[1].date, "1970-01-02");
+    assert_eq!(report.days[1].created, vec![task("#1", "Write docs")]);
+  }
+
+  #[test]
+  fn render_markdown_and_json() {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    let events = vec![at(10, created(1, "Write docs"))];
+    let report = Report::build(events, 0, 2 * DAY, utc);
+
+    assert_eq!(
+      report.render(ReportFormat::Markdown).unwrap(),
+      r#"# Report 1970-01-01 ~ 1970-01-02
+
+## 1970-01-01
+
+### Created
+
+- #1 Write docs
+
+## 1970-01-02
+
+No activity
+"#
+    );
+    let json: Report = serde_json::from_str(&report.render(ReportFormat::Json).unwrap()).unwrap();
+    assert_eq!(json, report);
+  }
+
+  #[tokio::test]
+  async fn read_events_from_repository() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::<TaskEvent>::new(fixture.path());
+    let now = Utc::now().timestamp_millis();
+    eventstore
+      .append(vec![
+        at(now, created(1, "Write docs")),
+        at(now, status(1, 2, TaskStatus::Done)),
+      ])
+      .await
+      .unwrap();
+    // committed later but occurred earlier, as a commit rewritten on sync.
+    eventstore
+      .append(vec![at(now - 1_000, created(2, "Read docs"))])
+      .await
+      .unwrap();
+
+    let utc = FixedOffset::east_opt(0).unwrap();
+    let report = Report::read(&fixture.repo(), now - DAY, now + DAY, utc).unwrap();
+
+    let created: Vec<_> = report.days.iter().flat_map(|x| &x.created).collect();
+    let completed: Vec<_> = report.days.iter().flat_map(|x| &x.completed).collect();
+    assert_eq!(
+      created,
+      vec![&task("#2", "Read docs"), &task("#1", "Write docs")]
+    );
+    assert_eq!(completed, vec![&task("#1", "Write docs")]);
+  }
+}
@@ -0,0 +1,11 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+  #[error(transparent)]
+  Git(#[from] crate::git::Error),
+  #[error(transparent)]
+  SerdeJson(#[from] serde_json::Error),
+  #[error(transparent)]
+  Eventstore(#[from] crate::eventsourcing::git::GitEventstoreError),
+  #[error(transparent)]
+  Domain(#[from] crate::domain::Error),
+}
@@ -0,0 +1,130 @@
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::domain::task::{Task, TaskEvent};
+use crate::eventsourcing::git::GitEventstore;
+use crate::eventsourcing::{Aggregate, Persisted, Timestamp};
+use crate::git::CommitReader;
+use crate::report::Error;
+
+/// Event of a task with the commit it is stored in, and the task right before and after it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct TaskHistoryEntry {
+  pub persisted: Persisted<TaskEvent>,
+  pub oid: String,
+  pub author_name: String,
+  pub author_email: String,
+  /// Commit time (milliseconds).
+  pub committed_at: Timestamp,
+  /// `None` when the task did not exist, e.g. before it is created or after it is deleted.
+  pub before: Option<Task>,
+  pub after: Option<Task>,
+}
+
+impl TaskHistoryEntry {
+  /// Reads all events of the aggregate, oldest first.
+  pub fn read(repo: &Repository, aggregate_id: &str) -> Result<Vec<Self>, Error> {
+    let mut commits = Vec::new();
+    for commit in CommitReader::new(repo)?.start_on_head() {
+      let commit = commit?;
+      let oid = commit.id.to_string();
+      let author_name = commit.author_name.to_owned();
+      let author_email = commit.author_email.to_owned();
+      let committed_at = commit.time * 1000;
+      let events: Vec<_> = GitEventstore::<TaskEvent>::commit_to_events(commit)?
+        .into_iter()
+        .filter(|x| x.aggregate_id == aggregate_id)
+        .collect();
+      if !events.is_empty() {
+        commits.push((oid, author_name, author_email, committed_at, events));
+      }
+    }
+
+    let mut state = None;
+    let mut entries = Vec::new();
+    for (oid, author_name, author_email, committed_at, events) in commits.into_iter().rev() {
+      for persisted in events {
+        let before = state.take();
+        let (_, after) = Task::apply_event(
+          before.clone(),
+          persisted.event.clone(),
+          persisted.occurred_at,
+        )?;
+        state = after.clone();
+        entries.push(Self {
+          persisted,
+          oid: oid.to_owned(),
+          author_name: author_name.to_owned(),
+          author_email: author_email.to_owned(),
+          committed_at,
+          before,
+          after,
+        });
+      }
+    }
+    Ok(entries)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use geeks_tracker_testing::git::FixtureRepository;
+
+  use crate::domain::task::{TaskCommand, TaskId, TaskStatus};
+  use crate::eventsourcing::{AggregateRoot, Event, Eventstore};
+
+  use super::*;
+
+  #[tokio::test]
+  async fn read_history_with_before_and_after() {
+    let fixture = FixtureRepository::default();
+    let eventstore = GitEventstore::new(fixture.path());
+    let mut root = AggregateRoot::<Task>::default();
+    let id = TaskId::new(1);
+    let commands = vec![
+      TaskCommand::Create {
+        title: "Write docs".to_string(),
+        status: None,
+        schedule: None,
+      },
+      TaskCommand::Create {
+        title: "Fix bug".to_string(),
+        status: None,
+        schedule: None,
+      },
+      TaskCommand::UpdateTitle {
+        id,
+        title: "Write more docs".to_string(),
+      },
+      TaskCommand::UpdateStatus {
+        id,
+        status: TaskStatus::Done,
+        force: false,
+      },
+    ];
+    for command in commands {
+      let persisted = root.execute_command(command).unwrap();
+      eventstore.append(vec![persisted]).await.unwrap();
+    }
+
+    let aggregate_id = root.resolve_id("#1").to_owned();
+    let history = TaskHistoryEntry::read(&fixture.repo(), &aggregate_id).unwrap();
+    let names: Vec<_> = history.iter().map(|x| x.persisted.event.name()).collect();
+    assert_eq!(
+      names,
+      vec!["task.created", "task.titleUpdated", "task.statusUpdated"]
+    );
+    assert_eq!(history[0].before, None);
+    assert_eq!(history[1].before.as_ref().unwrap().title, "Write docs");
+    assert_eq!(history[1].after.as_ref().unwrap().title, "Write more docs");
+    assert_eq!(history[2].after.as_ref().unwrap().status, TaskStatus::Done);
+    assert_eq!(history[2].after.as_ref(), root.get_state("#1"));
+    assert_eq!(
+      history[2].oid,
+      fixture.repo().head().unwrap().target().unwrap().to_string()
+    );
+  }
+}
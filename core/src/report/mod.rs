@@ -0,0 +1,7 @@
+pub use daily::*;
+pub use error::*;
+pub use history::*;
+
+mod daily;
+mod error;
+mod history;
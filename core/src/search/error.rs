@@ -0,0 +1,11 @@
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum Error {
+  #[error("unknown search field: {0}")]
+  UnknownField(String),
+  #[error("invalid task status: {0}")]
+  InvalidStatus(String),
+  #[error("invalid date: {0}")]
+  InvalidDate(String),
+  #[error("unterminated quote")]
+  UnterminatedQuote,
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use crate::domain::task::{Task, TaskEvent, TaskId, TaskStatus};
+use crate::eventsourcing::{AggregateRoot, Persisted, Timestamp};
+use crate::search::{SearchQuery, SearchTerm};
+
+const TITLE_WEIGHT: u32 = 4;
+const BODY_WEIGHT: u32 = 2;
+const HISTORY_WEIGHT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+  pub id: TaskId,
+  pub score: u32,
+}
+
+/// In-memory index of tasks, kept up to date by `apply` with persisted events.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+  documents: HashMap<String, Document>,
+}
+
+#[derive(Debug, Clone)]
+struct Document {
+  id: TaskId,
+  status: TaskStatus,
+  tags: Vec<String>,
+  created_at: Timestamp,
+  updated_at: Timestamp,
+  title: String,
+  /// Body and checklist items.
+  body: String,
+  /// Titles and bodies the task had before.
+  history: Vec<String>,
+}
+
+impl Document {
+  fn new(task: &Task) -> Self {
+    let mut body = task.body.clone().unwrap_or_default();
+    for item in &task.checklist {
+      body.push('\n');
+      body.push_str(&item.text);
+    }
+    Self {
+      id: task.id,
+      status: task.status.to_owned(),
+      tags: task.tags.to_owned(),
+      created_at: task.created_at,
+      updated_at: task.updated_at,
+      title: task.title.to_lowercase(),
+      body: body.to_lowercase(),
+      history: Vec::new(),
+    }
+  }
+
+  fn matches(&self, term: &SearchTerm) -> bool {
+    match term {
+      SearchTerm::Text(_) => true,
+      SearchTerm::Status(status) => &self.status == status,
+      SearchTerm::Tag(tag) => self.tags.contains(tag),
+      SearchTerm::Created(range) => range.contains(self.created_at),
+      SearchTerm::Updated(range) => range.contains(self.updated_at),
+    }
+  }
+
+  /// Weighted count of occurrences, 0 when not found anywhere.
+  fn score(&self, text: &str) -> u32 {
+    let count = |x: &str| x.matches(text).count() as u32;
+    count(&self.title) * TITLE_WEIGHT
+      + count(&self.body) * BODY_WEIGHT
+      + self.history.iter().map(|x| count(x)).sum::<u32>() * HISTORY_WEIGHT
+  }
+}
+
+impl SearchIndex {
+  pub fn build(root: &AggregateRoot<Task>) -> Self {
+    let documents = root
+      .states
+      .iter()
+      .map(|(id, task)| (id.to_owned(), Document::new(task)))
+      .collect();
+    Self { documents }
+  }
+
+  /// Re-indexes tasks of `events` from `root`, which the events are already applied to.
+  pub fn apply(&mut self, events: &[Persisted<TaskEvent>], root: &AggregateRoot<Task>) {
+    for persisted in events {
+      let id = &persisted.aggregate_id;
+      let previous = self.documents.remove(id);
+      let Some(task) = root.get_state(id) else {
+        continue;
+      };
+      let mut document = Document::new(task);
+      if let Some(previous) = previous {
+        document.history = previous.history;
+        for (before, after) in [
+          (previous.title, &document.title),
+          (previous.body, &document.body),
+        ] {
+          if !before.is_empty() && &before != after && !document.history.contains(&before) {
+            document.history.push(before);
+          }
+        }
+      }
+      self.documents.insert(id.to_owned(), document);
+    }
+  }
+
+  /// Tasks matching every term of the query, in order of relevance then recently updated.
+  pub fn search(&self, query: &SearchQuery) -> Vec<SearchHit> {
+    let texts: Vec<_> = query.texts().collect();
+    let mut hits: Vec<_> = self
+      .documents
+      .values()
+      .filter(|x| query.terms.iter().all(|term| x.matches(term)))
+      .filter_map(|x| {
+        let scores: Vec<_> = texts.iter().map(|text| x.score(text)).collect();
+        if scores.contains(&0) {
+          return None;
+        }
+        Some((x, scores.iter().sum::<u32>()))
+      })
+      .collect();
+    hits.sort_by(|(a, a_score), (b, b_score)| {
+      b_score
+        .cmp(a_score)
+        .then(b.updated_at.cmp(&a.updated_at))
+        .then(a.id.no.cmp(&b.id.no))
+    });
+    hits
+      .into_iter()
+      .map(|(x, score)| SearchHit { id: x.id, score })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::FixedOffset;
+
+  use crate::domain::task::TaskCommand;
+
+  use super::*;
+
+  fn query(text: &str) -> SearchQuery {
+    SearchQuery::parse(text, &FixedOffset::east_opt(0).unwrap()).unwrap()
+  }
+
+  fn ids(hits: Vec<SearchHit>) -> Vec<i32> {
+    hits.into_iter().map(|x| x.id.no).collect()
+  }
+
+  fn create(root: &mut AggregateRoot<Task>, title: &str) -> Persisted<TaskEvent> {
+    root
+      .execute_command(TaskCommand::Create {
+        title: title.to_string(),
+        status: None,
+        schedule: None,
+      })
+      .unwrap()
+  }
+
+  #[test]
+  fn rank_title_over_body() {
+    let mut root = AggregateRoot::<Task>::default();
+    create(&mut root, "Write release notes");
+    create(&mut root, "Deploy app");
+    root
+      .execute_command(TaskCommand::UpdateBody {
+        id: TaskId::new(1),
+        body: Some("deploy after review".to_string()),
+      })
+      .unwrap();
+    let index = SearchIndex::build(&root);
+
+    let hits = index.search(&query("deploy"));
+    assert_eq!(ids(hits.clone()), vec![2, 1]);
+    assert!(hits[0].score > hits[1].score);
+    assert_eq!(
+      ids(index.search(&query(r#""release notes" deploy"#))),
+      vec![1]
+    );
+    assert_eq!(ids(index.search(&query("created:>2000-01-01"))).len(), 2);
+    assert!(index.search(&query("created:<2000-01-01")).is_empty());
+  }
+
+  #[test]
+  fn update_incrementally() {
+    let mut root = AggregateRoot::<Task>::default();
+    let mut index = SearchIndex::default();
+    let created = create(&mut root, "Deploy app");
+    index.apply(&[created], &root);
+    assert_eq!(ids(index.search(&query("status:backlog deploy"))), vec![1]);
+
+    let events = vec![
+      root
+        .execute_command(TaskCommand::UpdateTitle {
+          id: TaskId::new(1),
+          title: "Ship app".to_string(),
+        })
+        .unwrap(),
+      root
+        .execute_command(TaskCommand::UpdateStatus {
+          id: TaskId::new(1),
+          status: TaskStatus::InProgress,
+          force: false,
+        })
+        .unwrap(),
+    ];
+    index.apply(&events, &root);
+    assert!(index.search(&query("status:backlog")).is_empty());
+    // old title is still searchable as history.
+    let hits = index.search(&query("status:in_progress deploy"));
+    assert_eq!(
+      hits,
+      vec![SearchHit {
+        id: TaskId::new(1),
+        score: HISTORY_WEIGHT
+      }]
+    );
+
+    let deleted = root
+      .execute_command(TaskCommand::Delete { id: TaskId::new(1) })
+      .unwrap();
+    index.apply(&[deleted], &root);
+    assert!(index.search(&query("ship")).is_empty());
+  }
+}
@@ -0,0 +1,7 @@
+pub use error::*;
+pub use index::*;
+pub use query::*;
+
+mod error;
+mod index;
+mod query;
@@ -0,0 +1,219 @@
+use std::str::FromStr;
+
+use chrono::{Days, FixedOffset, NaiveDate, TimeZone};
+
+use crate::domain::task::TaskStatus;
+use crate::eventsourcing::Timestamp;
+use crate::search::Error;
+
+/// Parsed search text, every term should match.
+///
+/// e.g. `status:in_progress "deploy" created:>2024-05-01`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+  pub terms: Vec<SearchTerm>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchTerm {
+  /// Lowercased word or quoted phrase, matched against title, body and history.
+  Text(String),
+  Status(TaskStatus),
+  Tag(String),
+  Created(TimeRange),
+  Updated(TimeRange),
+}
+
+/// `from` inclusive, `to` exclusive.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeRange {
+  pub from: Option<Timestamp>,
+  pub to: Option<Timestamp>,
+}
+
+impl TimeRange {
+  pub fn contains(&self, time: Timestamp) -> bool {
+    self.from.map_or(true, |x| x <= time) && self.to.map_or(true, |x| time < x)
+  }
+}
+
+impl SearchQuery {
+  /// Dates are read as days in `offset`.
+  pub fn parse(text: &str, offset: &FixedOffset) -> Result<Self, Error> {
+    let terms = split_words(text)?
+      .into_iter()
+      .map(|(word, quoted)| match quoted {
+        true => Ok(SearchTerm::Text(word.to_lowercase())),
+        false => parse_term(&word, offset),
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(Self { terms })
+  }
+
+  pub fn texts(&self) -> impl Iterator<Item = &str> {
+    self.terms.iter().filter_map(|x| match x {
+      SearchTerm::Text(text) => Some(text.as_str()),
+      _ => None,
+    })
+  }
+}
+
+fn parse_term(word: &str, offset: &FixedOffset) -> Result<SearchTerm, Error> {
+  let Some((field, value)) = word.split_once(':') else {
+    return Ok(SearchTerm::Text(word.to_lowercase()));
+  };
+  match field {
+    "status" => TaskStatus::from_str(value)
+      .map(SearchTerm::Status)
+      .map_err(|_| Error::InvalidStatus(value.to_string())),
+    "tag" => Ok(SearchTerm::Tag(value.to_string())),
+    "created" => parse_range(value, offset).map(SearchTerm::Created),
+    "updated" => parse_range(value, offset).map(SearchTerm::Updated),
+    _ => Err(Error::UnknownField(field.to_string())),
+  }
+}
+
+/// `>date`, `>=date`, `<date`, `<=date` or `date` for the whole day.
+fn parse_range(value: &str, offset: &FixedOffset) -> Result<TimeRange, Error> {
+  let (op, date) = ["<=", ">=", "<", ">"]
+    .into_iter()
+    .find_map(|op| value.strip_prefix(op).map(|date| (op, date)))
+    .unwrap_or(("", value));
+  let invalid = || Error::InvalidDate(value.to_string());
+  let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| invalid())?;
+  let start = start_of_day(date, offset).ok_or_else(invalid)?;
+  let end = date
+    .checked_add_days(Days::new(1))
+    .and_then(|x| start_of_day(x, offset))
+    .ok_or_else(invalid)?;
+  let range = match op {
+    ">" => TimeRange {
+      from: Some(end),
+      to: None,
+    },
+    ">=" => TimeRange {
+      from: Some(start),
+      to: None,
+    },
+    "<" => TimeRange {
+      from: None,
+      to: Some(start),
+    },
+    "<=" => TimeRange {
+      from: None,
+      to: Some(end),
+    },
+    _ => TimeRange {
+      from: Some(start),
+      to: Some(end),
+    },
+  };
+  Ok(range)
+}
+
+fn start_of_day(date: NaiveDate, offset: &FixedOffset) -> Option<Timestamp> {
+  let midnight = date.and_hms_opt(0, 0, 0)?;
+  offset
+    .from_local_datetime(&midnight)
+    .single()
+    .map(|x| x.timestamp_millis())
+}
+
+/// Splits by whitespace, keeping quoted phrases together. Returns words with whether quoted.
+fn split_words(text: &str) -> Result<Vec<(String, bool)>, Error> {
+  let mut words = Vec::new();
+  let mut chars = text.chars().peekable();
+  while let Some(&c) = chars.peek() {
+    if c.is_whitespace() {
+      chars.next();
+      continue;
+    }
+    if c == '"' {
+      chars.next();
+      let mut phrase = String::new();
+      loop {
+        match chars.next() {
+          Some('"') => break,
+          Some(c) => phrase.push(c),
+          None => return Err(Error::UnterminatedQuote),
+        }
+      }
+      if !phrase.trim().is_empty() {
+        words.push((phrase.trim().to_string(), true));
+      }
+      continue;
+    }
+    let mut word = String::new();
+    while let Some(c) = chars.next_if(|x| !x.is_whitespace()) {
+      word.push(c);
+    }
+    words.push((word, false));
+  }
+  Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DAY: Timestamp = 24 * 60 * 60 * 1000;
+
+  fn utc() -> FixedOffset {
+    FixedOffset::east_opt(0).unwrap()
+  }
+
+  #[test]
+  fn parse_fields_and_phrases() {
+    let query = SearchQuery::parse(r#"status:in_progress "Deploy app" Docs tag:work"#, &utc());
+    assert_eq!(
+      query.unwrap().terms,
+      vec![
+        SearchTerm::Status(TaskStatus::InProgress),
+        SearchTerm::Text("deploy app".to_string()),
+        SearchTerm::Text("docs".to_string()),
+        SearchTerm::Tag("work".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn parse_date_ranges() {
+    let parse = |x: &str| SearchQuery::parse(x, &utc()).unwrap().terms;
+    assert_eq!(
+      parse("created:>1970-01-02"),
+      vec![SearchTerm::Created(TimeRange {
+        from: Some(2 * DAY),
+        to: None,
+      })]
+    );
+    assert_eq!(
+      parse("updated:<=1970-01-02"),
+      vec![SearchTerm::Updated(TimeRange {
+        from: None,
+        to: Some(2 * DAY),
+      })]
+    );
+    assert_eq!(
+      parse("created:1970-01-02"),
+      vec![SearchTerm::Created(TimeRange {
+        from: Some(DAY),
+        to: Some(2 * DAY),
+      })]
+    );
+  }
+
+  #[test]
+  fn error_on_invalid_query() {
+    let parse = |x: &str| SearchQuery::parse(x, &utc()).unwrap_err();
+    assert_eq!(parse("owner:me"), Error::UnknownField("owner".to_string()));
+    assert_eq!(
+      parse("status:doing"),
+      Error::InvalidStatus("doing".to_string())
+    );
+    assert_eq!(
+      parse("created:>May"),
+      Error::InvalidDate(">May".to_string())
+    );
+    assert_eq!(parse(r#"status:done "deploy"#), Error::UnterminatedQuote);
+  }
+}
@@ -0,0 +1,267 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::domain::task::{get_next_task_id, Task, TaskCommand, TaskSchedule, TaskStatus};
+use crate::eventsourcing::AggregateRoot;
+use crate::snapshots::task::{parse_task_frontmatter, split_body};
+use crate::snapshots::Error;
+use crate::utils::parse_frontmatter;
+
+/// Fields read from a task file, `None` when the file does not tell.
+#[derive(Debug, Default, PartialEq)]
+struct EditedTask {
+  title: Option<String>,
+  body: Option<Option<String>>,
+  status: Option<TaskStatus>,
+  schedule: Option<Option<TaskSchedule>>,
+}
+
+/// Commands which apply a change made on task file `name` (e.g. `#3.md`) outside the app.
+/// `contents` is `None` when the file is removed.
+///
+/// Files written by the snapshot give no commands, so it is safe to call on any change.
+pub fn task_file_commands(
+  root: &AggregateRoot<Task>,
+  name: &str,
+  contents: Option<&str>,
+) -> Result<Vec<TaskCommand>, Error> {
+  let Some(stem) = name.strip_suffix(".md").filter(|x| !x.starts_with('.')) else {
+    return Ok(Vec::new());
+  };
+  let task = root.get_state(stem);
+  let Some(contents) = contents else {
+    return Ok(
+      task
+        .map(|x| TaskCommand::Delete { id: x.id })
+        .into_iter()
+        .collect(),
+    );
+  };
+  let edited = parse(name, contents)?;
+  let commands = match task {
+    Some(task) => update_commands(task, edited),
+    None => create_commands(root, stem, edited),
+  };
+  Ok(commands)
+}
+
+fn update_commands(task: &Task, edited: EditedTask) -> Vec<TaskCommand> {
+  let id = task.id;
+  let mut commands = Vec::new();
+  if let Some(title) = edited.title.filter(|x| x != &task.title) {
+    commands.push(TaskCommand::UpdateTitle { id, title });
+  }
+  if let Some(body) = edited.body.filter(|x| x != &task.body) {
+    commands.push(TaskCommand::UpdateBody { id, body });
+  }
+  if let Some(status) = edited.status.filter(|x| x != &task.status) {
+    commands.push(TaskCommand::UpdateStatus {
+      id,
+      status,
+      force: false,
+    });
+  }
+  if let Some(schedule) = edited.schedule.filter(|x| x != &task.schedule) {
+    commands.push(TaskCommand::UpdateSchedule { id, schedule });
+  }
+  commands
+}
+
+fn create_commands(root: &AggregateRoot<Task>, stem: &str, edited: EditedTask) -> Vec<TaskCommand> {
+  let mut commands = vec![TaskCommand::Create {
+    title: edited.title.unwrap_or_else(|| stem.to_string()),
+    status: edited.status,
+    schedule: edited.schedule.flatten(),
+  }];
+  if let Some(body) = edited.body.flatten() {
+    // commands run in order, so the task takes the next number.
+    commands.push(TaskCommand::UpdateBody {
+      id: get_next_task_id(root),
+      body: Some(body),
+    });
+  }
+  commands
+}
+
+/// Reads frontmatter written by the snapshot, then `## Title`, `## Status` and `## Body` sections,
+/// which win when edited. A file without any is read as a title line and body, like a plain note.
+fn parse(name: &str, contents: &str) -> Result<EditedTask, Error> {
+  let path = Path::new(name);
+  let (frontmatter, markdown) = parse_frontmatter(path, contents)?;
+  let has_frontmatter = frontmatter.is_some();
+  let (markdown, body) = split_body(markdown);
+  let mut edited = match frontmatter {
+    Some(data) => {
+      let data = parse_task_frontmatter(path, data, body)?;
+      EditedTask {
+        title: Some(data.state.title),
+        body: Some(data.state.body),
+        status: Some(data.state.status),
+        schedule: Some(data.state.schedule),
+      }
+    }
+    None => EditedTask {
+      body: body.map(|x| Some(x.trim().to_string()).filter(|x| !x.is_empty())),
+      ..Default::default()
+    },
+  };
+  let sections = sections(markdown);
+  let section = |name: &str| {
+    sections
+      .iter()
+      .find(|(x, _)| x == name)
+      .map(|(_, x)| x.to_owned())
+  };
+  if let Some(title) = section("Title").filter(|x| !x.is_empty()) {
+    edited.title = Some(title);
+  }
+  if let Some(status) = section("Status") {
+    let status = status.trim_matches('"');
+    let status = TaskStatus::from_str(status)
+      .map_err(|_| Error::InvalidTaskFile(format!("unknown status: {}", status)))?;
+    edited.status = Some(status);
+  }
+  if !has_frontmatter && sections.is_empty() && body.is_none() {
+    let mut lines = markdown.trim().splitn(2, '\n');
+    let title = lines.next().unwrap_or("").trim_start_matches('#').trim();
+    let body = lines.next().unwrap_or("").trim();
+    edited.title = Some(title.to_string()).filter(|x| !x.is_empty());
+    edited.body = Some(Some(body.to_string()).filter(|x| !x.is_empty()));
+  }
+  Ok(edited)
+}
+
+/// `## Name` sections of markdown with their trimmed contents.
+fn sections(markdown: &str) -> Vec<(String, String)> {
+  let mut sections: Vec<(String, String)> = Vec::new();
+  for line in markdown.lines() {
+    match line.strip_prefix("## ") {
+      Some(name) => sections.push((name.trim().to_string(), String::new())),
+      None => {
+        if let Some((_, contents)) = sections.last_mut() {
+          contents.push_str(line);
+          contents.push('\n');
+        }
+      }
+    }
+  }
+  for (_, contents) in sections.iter_mut() {
+    *contents = contents.trim().to_string();
+  }
+  sections
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use crate::domain::task::TaskId;
+  use crate::eventsourcing::Aggregate;
+  use crate::snapshots::SnapshotData;
+
+  use super::*;
+
+  fn root() -> AggregateRoot<Task> {
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Write docs".to_string())
+      .status(TaskStatus::Queue)
+      .build();
+    let states = HashMap::from([(task.id(), task)]);
+    let versions = HashMap::from([("#1".to_string(), 1)]);
+    AggregateRoot::new(states, versions)
+  }
+
+  fn file(task: &Task, title: &str, status: &str) -> String {
+    let data = SnapshotData {
+      id: task.id(),
+      version: 1,
+      state: task,
+    };
+    format!(
+      "---\n{}---\n\n## Title\n{}\n\n## Status\n{}\n",
+      serde_yaml::to_string(&data).unwrap(),
+      title,
+      status
+    )
+  }
+
+  #[test]
+  fn no_commands_for_unchanged_file() {
+    let root = root();
+    let task = root.get_state("#1").unwrap();
+    let raw = file(task, "Write docs", r#""queue""#);
+    assert_eq!(
+      task_file_commands(&root, "#1.md", Some(&raw)).unwrap(),
+      vec![]
+    );
+    // files of editors, e.g. swap files, are not tasks.
+    assert_eq!(task_file_commands(&root, ".#1.md", None).unwrap(), vec![]);
+    assert_eq!(task_file_commands(&root, "#1.md~", None).unwrap(), vec![]);
+  }
+
+  #[test]
+  fn update_edited_fields() {
+    let root = root();
+    let task = root.get_state("#1").unwrap();
+    let raw = file(task, "Write more docs", "done") + "\n## Body\nGuide for users\n";
+    assert_eq!(
+      task_file_commands(&root, "#1.md", Some(&raw)).unwrap(),
+      vec![
+        TaskCommand::UpdateTitle {
+          id: TaskId::new(1),
+          title: "Write more docs".to_string(),
+        },
+        TaskCommand::UpdateBody {
+          id: TaskId::new(1),
+          body: Some("Guide for users".to_string()),
+        },
+        TaskCommand::UpdateStatus {
+          id: TaskId::new(1),
+          status: TaskStatus::Done,
+          force: false,
+        },
+      ]
+    );
+
+    let raw = file(task, "Write docs", "doing");
+    assert!(matches!(
+      task_file_commands(&root, "#1.md", Some(&raw)),
+      Err(Error::InvalidTaskFile(_))
+    ));
+  }
+
+  #[test]
+  fn create_new_file_and_delete_removed_one() {
+    let root = root();
+    assert_eq!(
+      task_file_commands(&root, "milk.md", Some("# Buy milk\n\n2 liters\n")).unwrap(),
+      vec![
+        TaskCommand::Create {
+          title: "Buy milk".to_string(),
+          status: None,
+          schedule: None,
+        },
+        TaskCommand::UpdateBody {
+          id: TaskId::new(2),
+          body: Some("2 liters".to_string()),
+        },
+      ]
+    );
+    assert_eq!(
+      task_file_commands(&root, "milk.md", Some("## Status\nin_progress\n")).unwrap(),
+      vec![TaskCommand::Create {
+        title: "milk".to_string(),
+        status: Some(TaskStatus::InProgress),
+        schedule: None,
+      }]
+    );
+
+    assert_eq!(
+      task_file_commands(&root, "#1.md", None).unwrap(),
+      vec![TaskCommand::Delete { id: TaskId::new(1) }]
+    );
+    // removed by the snapshot after the task is deleted.
+    assert_eq!(task_file_commands(&root, "#2.md", None).unwrap(), vec![]);
+  }
+}
@@ -0,0 +1,13 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+  #[error(transparent)]
+  Io(#[from] std::io::Error),
+  #[error(transparent)]
+  SerdeJson(#[from] serde_json::Error),
+  #[error(transparent)]
+  SerdeYaml(#[from] serde_yaml::Error),
+  #[error(transparent)]
+  Frontmatter(#[from] crate::utils::FrontmatterError),
+  #[error("invalid task file: {0}")]
+  InvalidTaskFile(String),
+}
@@ -0,0 +1,9 @@
+pub use edit::*;
+pub use error::*;
+pub use task::*;
+pub use verify::*;
+
+mod edit;
+mod error;
+mod task;
+mod verify;
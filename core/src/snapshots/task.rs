@@ -0,0 +1,585 @@
+use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use tokio::fs;
+
+use crate::domain::task::{Task, TaskId};
+use crate::eventsourcing::{Aggregate, AggregateRoot, Snapshot, Version};
+use crate::snapshots::Error;
+use crate::utils::{parse_frontmatter, write_frontmatter};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotData<T> {
+  pub id: String,
+  pub version: Version,
+  pub state: T,
+}
+
+/// Heading of the body, which is the last section of a task file and runs to its end.
+const BODY_HEADING: &str = "## Body";
+
+/// Keys of task timestamps written as RFC 3339 dates instead of milliseconds.
+const TIMESTAMP_KEYS: [&str; 6] = [
+  "createdAt",
+  "updatedAt",
+  "backlogAt",
+  "inProgressAt",
+  "queueAt",
+  "doneAt",
+];
+
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct TaskSnapshot {
+  dir: PathBuf,
+  /// Where `replace` builds the next tasks dir. It is inside git dir like the event index,
+  /// so watchers of the working dir and snapshot commits never see half written files.
+  work_dir: PathBuf,
+}
+
+impl TaskSnapshot {
+  pub async fn new(basedir: &Path) -> Result<Self, Error> {
+    let snapshot = Self {
+      dir: basedir.join("tasks"),
+      work_dir: basedir.join(".git").join("geeks-tracker"),
+    };
+    snapshot.recover().await?;
+    fs::create_dir_all(&snapshot.dir).await?;
+    Ok(snapshot)
+  }
+
+  fn staging_dir(&self) -> PathBuf {
+    self.work_dir.join("tasks.staging")
+  }
+
+  fn old_dir(&self) -> PathBuf {
+    self.work_dir.join("tasks.old")
+  }
+
+  /// Puts back the previous tasks dir when `replace` stopped after moving it away.
+  async fn recover(&self) -> Result<(), Error> {
+    let old = self.old_dir();
+    if !fs::try_exists(&old).await? {
+      return Ok(());
+    }
+    match fs::try_exists(&self.dir).await? {
+      true => fs::remove_dir_all(&old).await?,
+      false => fs::rename(&old, &self.dir).await?,
+    }
+    Ok(())
+  }
+
+  /// Writes files of tasks `ids` only, and removes the files of the ones deleted. Other files
+  /// are left as they are, e.g. one being edited by hand.
+  pub async fn save_tasks<'a>(
+    &self,
+    root: &AggregateRoot<Task>,
+    ids: impl IntoIterator<Item = &'a str>,
+  ) -> Result<(), Error> {
+    let ids: HashSet<_> = ids.into_iter().collect();
+    if ids.iter().any(|x| !root.states.contains_key(*x)) {
+      // removed first, as the number of a deleted task may be given to a new one.
+      for file in TaskFile::find_all(&self.dir).await? {
+        let deleted = match file.load().await {
+          Ok(Some(data)) => ids.contains(data.id.as_str()) && !root.states.contains_key(&data.id),
+          _ => false,
+        };
+        if deleted {
+          fs::remove_file(&file.filepath).await?;
+        }
+      }
+    }
+    for id in ids {
+      if let Some(state) = root.states.get(id) {
+        let version = root.versions.get(id).cloned().unwrap_or(0);
+        TaskFile::new(state, &self.dir).save(state, version).await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Removes file `name` a task was created from, unless it is the file of a task now.
+  pub async fn remove_new_file(&self, root: &AggregateRoot<Task>, name: &str) -> Result<(), Error> {
+    let stem = name.strip_suffix(".md").unwrap_or(name);
+    if root.get_state(stem).is_some() {
+      return Ok(());
+    }
+    match fs::remove_file(self.dir.join(name)).await {
+      Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+      _ => Ok(()),
+    }
+  }
+}
+
+#[async_trait]
+impl Snapshot<Task> for TaskSnapshot {
+  type Error = Error;
+
+  async fn load(&self) -> Result<AggregateRoot<Task>, Self::Error> {
+    self.recover().await?;
+    let files = TaskFile::find_all(&self.dir).await?;
+    let mut states: HashMap<String, Task> = HashMap::with_capacity(100);
+    let mut versions: HashMap<String, Version> = HashMap::with_capacity(100);
+
+    // files of editors (e.g. `.#1.md`) and notes added by hand are not task files.
+    for file in files.iter().filter(|x| x.number().is_some()) {
+      let Some(data) = file.load().await? else {
+        continue;
+      };
+      states.insert(data.id.to_owned(), data.state);
+      versions.insert(data.id.to_owned(), data.version);
+    }
+
+    Ok(AggregateRoot::new(states, versions))
+  }
+
+  async fn save(&self, root: &AggregateRoot<Task>) -> Result<(), Self::Error> {
+    for (id, state) in root.states.iter() {
+      let file = TaskFile::new(state, &self.dir);
+      let version = root.versions.get(id).cloned().unwrap();
+      file.save(state, version).await?;
+    }
+    // files written for deleted or renamed tasks would be loaded again, other files are kept.
+    let numbers: HashSet<_> = root.states.values().map(|x| x.id.no).collect();
+    for file in TaskFile::find_all(&self.dir).await? {
+      let stale = file.number().is_some_and(|x| !numbers.contains(&x.no))
+        && matches!(file.load().await, Ok(Some(_)));
+      if stale {
+        fs::remove_file(&file.filepath).await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Writes all files into a staging dir first, then swaps it with the tasks dir.
+  async fn replace(&self, root: &AggregateRoot<Task>) -> Result<(), Self::Error> {
+    self.recover().await?;
+    let (staging, old) = (self.staging_dir(), self.old_dir());
+    if fs::try_exists(&staging).await? {
+      fs::remove_dir_all(&staging).await?;
+    }
+    fs::create_dir_all(&staging).await?;
+    Self {
+      dir: staging.clone(),
+      work_dir: self.work_dir.clone(),
+    }
+    .save(root)
+    .await?;
+
+    fs::rename(&self.dir, &old).await?;
+    fs::rename(&staging, &self.dir).await?;
+    fs::remove_dir_all(&old).await?;
+    Ok(())
+  }
+}
+
+#[derive(Debug, PartialEq)]
+struct TaskFile {
+  filepath: PathBuf,
+}
+
+impl TaskFile {
+  pub fn new(state: &Task, dir: &Path) -> Self {
+    let filepath = dir.join(format!("{}.md", state.id));
+    Self { filepath }
+  }
+
+  fn name(&self) -> Option<&str> {
+    self.filepath.file_name()?.to_str()
+  }
+
+  /// Task number of `#1.md` like file name.
+  fn number(&self) -> Option<TaskId> {
+    let no = self.name()?.strip_suffix(".md")?.strip_prefix('#')?;
+    no.parse().ok().map(TaskId::new)
+  }
+
+  pub async fn find_all(dir: &Path) -> Result<Vec<Self>, Error> {
+    let mut files = vec![];
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+      let metadata = entry.metadata().await?;
+      if metadata.is_file() {
+        files.push(Self {
+          filepath: entry.path(),
+        })
+      }
+    }
+    Ok(files)
+  }
+
+  /// Reads the task written in the file, `None` when it has no frontmatter.
+  pub async fn load(&self) -> Result<Option<SnapshotData<Task>>, Error> {
+    let raw = fs::read_to_string(&self.filepath).await?;
+    let (Some(data), markdown) = parse_frontmatter(&self.filepath, &raw)? else {
+      return Ok(None);
+    };
+    let (_, body) = split_body(markdown);
+    parse_task_frontmatter(&self.filepath, data, body).map(Some)
+  }
+
+  pub async fn save(&self, state: &Task, version: Version) -> Result<(), Error> {
+    // body is written as markdown below, not to repeat it in frontmatter.
+    let mut value = serde_yaml::to_value(state)?;
+    if let Value::Mapping(mapping) = &mut value {
+      mapping.remove("body");
+    }
+    map_timestamps(&mut value, &render_timestamp)?;
+    let data = SnapshotData {
+      id: state.id(),
+      version,
+      state: value,
+    };
+    let mut contents = format!(
+      r#"
+## Title
+{title}
+
+## Status
+{status}
+"#,
+      title = state.title,
+      status = serde_json::to_string(&state.status)?,
+    );
+    if !state.checklist.is_empty() {
+      contents.push_str("\n## Checklist\n");
+      for item in &state.checklist {
+        let mark = if item.checked { "x" } else { " " };
+        contents.push_str(&format!("- [{}] {}\n", mark, item.text));
+      }
+    }
+    if let Some(body) = &state.body {
+      contents.push_str(&format!("\n{}\n{}\n", BODY_HEADING, body));
+    }
+    if let Some(dirname) = self.filepath.parent() {
+      fs::create_dir_all(dirname).await?;
+    }
+    fs::write(&self.filepath, write_frontmatter(&data, &contents)?).await?;
+    Ok(())
+  }
+}
+
+/// Splits markdown after frontmatter into sections and the body of `## Body` section.
+pub(crate) fn split_body(markdown: &str) -> (&str, Option<&str>) {
+  let mut offset = 0;
+  for line in markdown.split_inclusive('\n') {
+    if line.trim_end() == BODY_HEADING {
+      let body = &markdown[offset + line.len()..];
+      let body = body
+        .strip_suffix('\n')
+        .map(|x| x.strip_suffix('\r').unwrap_or(x))
+        .unwrap_or(body);
+      return (&markdown[..offset], Some(body));
+    }
+    offset += line.len();
+  }
+  (markdown, None)
+}
+
+/// Reads frontmatter of a task file, with `body` of its markdown.
+/// Files written before the body moved out of frontmatter have it in their state instead.
+pub(crate) fn parse_task_frontmatter(
+  path: &Path,
+  mut data: SnapshotData<Value>,
+  body: Option<&str>,
+) -> Result<SnapshotData<Task>, Error> {
+  map_timestamps(&mut data.state, &parse_timestamp)?;
+  if let (Value::Mapping(mapping), Some(body)) = (&mut data.state, body) {
+    mapping.insert("body".into(), body.into());
+  }
+  Ok(SnapshotData {
+    id: data.id,
+    version: data.version,
+    state: serde_yaml::from_value(data.state)
+      .map_err(|e| Error::InvalidTaskFile(format!("{}: {}", path.display(), e)))?,
+  })
+}
+
+fn map_timestamps<F>(state: &mut Value, f: &F) -> Result<(), Error>
+where
+  F: Fn(&mut Value) -> Result<(), Error>,
+{
+  let Value::Mapping(state) = state else {
+    return Ok(());
+  };
+  for key in TIMESTAMP_KEYS {
+    if let Some(value) = state.get_mut(key) {
+      f(value)?;
+    }
+  }
+  if let Some(Value::Mapping(schedule)) = state.get_mut("schedule") {
+    if let Some(value) = schedule.get_mut("at") {
+      f(value)?;
+    }
+  }
+  if let Some(Value::Sequence(sessions)) = state.get_mut("sessions") {
+    for session in sessions.iter_mut().filter_map(|x| x.as_mapping_mut()) {
+      for key in ["startedAt", "endedAt"] {
+        if let Some(value) = session.get_mut(key) {
+          f(value)?;
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+fn render_timestamp(value: &mut Value) -> Result<(), Error> {
+  if let Some(millis) = value.as_i64() {
+    let at = Utc
+      .timestamp_millis_opt(millis)
+      .single()
+      .ok_or_else(|| Error::InvalidTaskFile(format!("invalid timestamp: {}", millis)))?;
+    *value = at.to_rfc3339_opts(SecondsFormat::Millis, true).into();
+  }
+  Ok(())
+}
+
+/// Reads RFC 3339 dates back to milliseconds. Files in old layout have numbers, kept as is.
+fn parse_timestamp(value: &mut Value) -> Result<(), Error> {
+  if let Some(text) = value.as_str() {
+    let at = DateTime::parse_from_rfc3339(text)
+      .map_err(|_| Error::InvalidTaskFile(format!("invalid timestamp: {}", text)))?;
+    *value = at.timestamp_millis().into();
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use tokio::fs;
+
+  use geeks_tracker_testing::tempdir;
+
+  use crate::domain::recurrence::{Recurrence, Weekday};
+  use crate::domain::task::{ChecklistItem, TaskSchedule, TaskStatus};
+
+  use super::*;
+
+  #[tokio::test]
+  async fn read_files_from_dir() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    fs::create_dir_all(dir.path().join("tasks")).await.unwrap();
+    fs::write(dir.path().join("tasks/#1.md"), "content")
+      .await
+      .unwrap();
+    fs::write(dir.path().join("tasks/#2.md"), "content")
+      .await
+      .unwrap();
+    let files = TaskFile::find_all(&dir.path().join("tasks")).await.unwrap();
+    assert_eq!(files.len(), 2);
+  }
+
+  fn root(nos: &[i32]) -> AggregateRoot<Task> {
+    let task = |no: i32| {
+      Task::builder()
+        .id(TaskId::new(no))
+        .title("Hello".to_string())
+        .created_at(0)
+        .updated_at(0)
+        .build()
+    };
+    let states = nos.iter().map(|x| (task(*x).id(), task(*x))).collect();
+    let versions = nos.iter().map(|x| (task(*x).id(), 1)).collect();
+    AggregateRoot::new(states, versions)
+  }
+
+  #[tokio::test]
+  async fn save_only_given_tasks_and_keep_other_files() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let snapshot = TaskSnapshot::new(dir.path()).await.unwrap();
+    snapshot.save(&root(&[1, 2, 3])).await.unwrap();
+    let path = |name: &str| dir.path().join("tasks").join(name);
+    for name in ["milk.md", ".#1.md", ".DS_Store"] {
+      fs::write(path(name), "Buy milk\n").await.unwrap();
+    }
+    let edited = fs::read_to_string(path("#1.md")).await.unwrap() + "\n## Body\nEdited\n";
+    fs::write(path("#1.md"), &edited).await.unwrap();
+
+    let loaded = snapshot.load().await.unwrap();
+    let mut ids = loaded.ids();
+    ids.sort();
+    assert_eq!(ids, vec!["#1", "#2", "#3"]);
+
+    snapshot.save_tasks(&root(&[1, 3]), ["#2"]).await.unwrap();
+    assert!(!path("#2.md").exists());
+    assert_eq!(fs::read_to_string(path("#1.md")).await.unwrap(), edited);
+
+    snapshot.save(&root(&[1])).await.unwrap();
+    assert!(!path("#3.md").exists());
+    for name in ["milk.md", ".#1.md", ".DS_Store"] {
+      assert!(path(name).exists(), "{}", name);
+    }
+    snapshot
+      .remove_new_file(&root(&[1]), "milk.md")
+      .await
+      .unwrap();
+    snapshot
+      .remove_new_file(&root(&[1]), "#1.md")
+      .await
+      .unwrap();
+    assert!(!path("milk.md").exists());
+    assert!(path("#1.md").exists());
+  }
+
+  #[tokio::test]
+  async fn replace_in_git_dir_and_recover() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let snapshot = TaskSnapshot::new(dir.path()).await.unwrap();
+    snapshot.save(&root(&[1, 2])).await.unwrap();
+    snapshot.replace(&root(&[1])).await.unwrap();
+    assert_eq!(snapshot.load().await.unwrap().ids(), vec!["#1"]);
+    let mut entries = std::fs::read_dir(dir.path()).unwrap();
+    assert!(entries.all(|x| x.unwrap().file_name() != "tasks.staging"));
+
+    // stopped right after moving the tasks dir away.
+    fs::create_dir_all(dir.path().join(".git/geeks-tracker"))
+      .await
+      .unwrap();
+    fs::rename(
+      dir.path().join("tasks"),
+      dir.path().join(".git/geeks-tracker/tasks.old"),
+    )
+    .await
+    .unwrap();
+    let snapshot = TaskSnapshot::new(dir.path()).await.unwrap();
+    assert_eq!(snapshot.load().await.unwrap().ids(), vec!["#1"]);
+    assert!(!dir.path().join(".git/geeks-tracker/tasks.old").exists());
+  }
+
+  #[tokio::test]
+  async fn save_and_parse_file() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Hello".to_string())
+      .build();
+    let file = TaskFile::new(&task, dir.path());
+    file.save(&task, 1).await.unwrap();
+    let parsed = file.load().await.unwrap().unwrap();
+    assert_eq!(parsed.id, "#1");
+    assert_eq!(parsed.version, 1);
+    assert_eq!(parsed.state, task);
+  }
+
+  #[tokio::test]
+  async fn save_and_parse_recurring_schedule() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let schedule = TaskSchedule::builder()
+      .at(1000)
+      .status(TaskStatus::InProgress)
+      .repeat(Some(Recurrence::Weekly {
+        weekdays: Weekday::WEEKDAYS.to_vec(),
+        hour: 9,
+        minute: 0,
+      }))
+      .build();
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Stand-up".to_string())
+      .schedule(Some(schedule))
+      .build();
+    let file = TaskFile::new(&task, dir.path());
+    file.save(&task, 1).await.unwrap();
+    let parsed = file.load().await.unwrap().unwrap();
+    assert_eq!(parsed.state, task);
+  }
+
+  #[tokio::test]
+  async fn render_checklist_items() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let item = |id: u32, text: &str, checked: bool| ChecklistItem {
+      id,
+      text: text.to_string(),
+      checked,
+    };
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Release".to_string())
+      .checklist(vec![item(1, "Write notes", true), item(2, "Tag", false)])
+      .build();
+    let file = TaskFile::new(&task, dir.path());
+    file.save(&task, 1).await.unwrap();
+
+    let raw = fs::read_to_string(&file.filepath).await.unwrap();
+    assert!(raw.ends_with("\n## Checklist\n- [x] Write notes\n- [ ] Tag\n"));
+    assert_eq!(file.load().await.unwrap().unwrap().state, task);
+  }
+
+  #[tokio::test]
+  async fn render_body_as_markdown() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let body = "Steps\n\n## Install\n---\nrun `make`\n";
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Setup".to_string())
+      .body(Some(body.to_string()))
+      .created_at(1704067200000)
+      .done_at(Some(1704067200123))
+      .build();
+    let file = TaskFile::new(&task, dir.path());
+    file.save(&task, 1).await.unwrap();
+
+    let raw = fs::read_to_string(&file.filepath).await.unwrap();
+    assert!(raw.ends_with(&format!("\n## Body\n{}\n", body)));
+    assert!(raw.contains("createdAt: 2024-01-01T00:00:00.000Z\n"));
+    assert!(raw.contains("doneAt: 2024-01-01T00:00:00.123Z\n"));
+    assert!(!raw.contains("body:"));
+    assert_eq!(file.load().await.unwrap().unwrap().state, task);
+  }
+
+  #[tokio::test]
+  async fn load_file_saved_with_crlf() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Hello".to_string())
+      .body(Some("World".to_string()))
+      .build();
+    let file = TaskFile::new(&task, dir.path());
+    file.save(&task, 1).await.unwrap();
+    let raw = fs::read_to_string(&file.filepath).await.unwrap();
+    let raw = format!("\u{feff}{}", raw.replace('\n', "\r\n"));
+    fs::write(&file.filepath, raw).await.unwrap();
+    assert_eq!(file.load().await.unwrap().unwrap().state, task);
+
+    fs::write(&file.filepath, "---\nid: [\n---\n")
+      .await
+      .unwrap();
+    let e = file.load().await.unwrap_err();
+    assert!(e.to_string().contains("#1.md:2: "), "{}", e);
+  }
+
+  #[tokio::test]
+  async fn load_file_in_old_layout() {
+    let dir = tempdir::TempDir::new("workspace").unwrap();
+    let task = Task::builder()
+      .id(TaskId::new(1))
+      .title("Hello".to_string())
+      .body(Some("World".to_string()))
+      .build();
+    let data = SnapshotData {
+      id: task.id(),
+      version: 2,
+      state: &task,
+    };
+    let raw = format!(
+      "---\n{}---\n\n## Title\nHello\n\n## Status\n\"backlog\"\n",
+      serde_yaml::to_string(&data).unwrap()
+    );
+    fs::write(dir.path().join("#1.md"), raw).await.unwrap();
+
+    let parsed = TaskFile::new(&task, dir.path())
+      .load()
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(parsed.version, 2);
+    assert_eq!(parsed.state, task);
+  }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::eventsourcing::{Aggregate, AggregateRoot, Version};
+
+/// Difference between the loaded snapshot and the state replayed from all events.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SnapshotDiff {
+  /// Aggregate has events but no snapshot.
+  Missing {
+    id: String,
+  },
+  /// Snapshot of an aggregate without events.
+  Unknown {
+    id: String,
+  },
+  VersionMismatch {
+    id: String,
+    snapshot: Version,
+    replayed: Version,
+  },
+  StateMismatch {
+    id: String,
+  },
+}
+
+impl SnapshotDiff {
+  pub fn id(&self) -> &str {
+    match self {
+      Self::Missing { id }
+      | Self::Unknown { id }
+      | Self::VersionMismatch { id, .. }
+      | Self::StateMismatch { id } => id,
+    }
+  }
+}
+
+impl fmt::Display for SnapshotDiff {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Missing { .. } => write!(f, "snapshot is missing"),
+      Self::Unknown { .. } => write!(f, "snapshot has no events"),
+      Self::VersionMismatch {
+        snapshot, replayed, ..
+      } => write!(
+        f,
+        "version is v{} but events are up to v{}",
+        snapshot, replayed
+      ),
+      Self::StateMismatch { .. } => write!(f, "state differs from events"),
+    }
+  }
+}
+
+/// Compares each aggregate of `snapshot` with `replayed`, ordered by id.
+pub fn diff_snapshot<T>(
+  snapshot: &AggregateRoot<T>,
+  replayed: &AggregateRoot<T>,
+) -> Vec<SnapshotDiff>
+where
+  T: Aggregate + PartialEq,
+{
+  let ids: BTreeSet<_> = snapshot
+    .states
+    .keys()
+    .chain(replayed.states.keys())
+    .collect();
+  ids
+    .into_iter()
+    .filter_map(|id| {
+      let id = id.to_owned();
+      let (Some(state), Some(expected)) = (snapshot.states.get(&id), replayed.states.get(&id))
+      else {
+        return Some(match snapshot.states.contains_key(&id) {
+          true => SnapshotDiff::Unknown { id },
+          false => SnapshotDiff::Missing { id },
+        });
+      };
+      let version = snapshot.versions.get(&id).cloned().unwrap_or(0);
+      let replayed_version = replayed.versions.get(&id).cloned().unwrap_or(0);
+      if version != replayed_version {
+        return Some(SnapshotDiff::VersionMismatch {
+          id,
+          snapshot: version,
+          replayed: replayed_version,
+        });
+      }
+      (state != expected).then_some(SnapshotDiff::StateMismatch { id })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use crate::domain::task::{Task, TaskId};
+
+  use super::*;
+
+  fn root(tasks: &[(i32, &str, Version)]) -> AggregateRoot<Task> {
+    let states: HashMap<_, _> = tasks
+      .iter()
+      .map(|(no, title, _)| {
+        let task = Task::builder()
+          .id(TaskId::new(*no))
+          .title(title.to_string())
+          .created_at(0)
+          .updated_at(0)
+          .build();
+        (task.id(), task)
+      })
+      .collect();
+    let versions = tasks
+      .iter()
+      .map(|(no, _, version)| (TaskId::new(*no).to_string(), *version))
+      .collect();
+    AggregateRoot::new(states, versions)
+  }
+
+  #[test]
+  fn report_differences() {
+    let snapshot = root(&[
+      (1, "Hello", 1),
+      (2, "Edited", 2),
+      (3, "Old", 1),
+      (5, "Gone", 1),
+    ]);
+    let replayed = root(&[
+      (1, "Hello", 1),
+      (2, "Hello", 2),
+      (3, "Old", 2),
+      (4, "New", 1),
+    ]);
+    assert_eq!(
+      diff_snapshot(&snapshot, &replayed),
+      vec![
+        SnapshotDiff::StateMismatch {
+          id: "#2".to_string()
+        },
+        SnapshotDiff::VersionMismatch {
+          id: "#3".to_string(),
+          snapshot: 1,
+          replayed: 2,
+        },
+        SnapshotDiff::Missing {
+          id: "#4".to_string()
+        },
+        SnapshotDiff::Unknown {
+          id: "#5".to_string()
+        },
+      ]
+    );
+    assert!(diff_snapshot(&replayed, &replayed).is_empty());
+  }
+}
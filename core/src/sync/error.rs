@@ -0,0 +1,23 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+  #[error("remote is not configured")]
+  NoRemote,
+  #[error("remote is changed while syncing, try again")]
+  RemoteChanged,
+  #[error("push is rejected: {0}")]
+  Rejected(String),
+  #[error(transparent)]
+  Git(#[from] crate::git::Error),
+  #[error(transparent)]
+  Eventstore(#[from] crate::eventsourcing::git::GitEventstoreError),
+  #[error(transparent)]
+  Snapshot(#[from] crate::snapshots::Error),
+  #[error(transparent)]
+  Domain(#[from] crate::domain::Error),
+}
+
+impl From<git2::Error> for Error {
+  fn from(e: git2::Error) -> Self {
+    Self::Git(e.into())
+  }
+}
@@ -0,0 +1,537 @@
+use std::collections::HashMap;
+
+use git2::{Oid, Repository};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::domain::task::{get_next_task_id, Task, TaskEvent, TaskId, TaskUid};
+use crate::eventsourcing::git::{EventCommitBody, GitEventstore, EVENT_MSG};
+use crate::eventsourcing::{AggregateRoot, Persisted, Timestamp};
+use crate::git::CommitReader;
+use crate::sync::Error;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[typeshare]
+pub enum SyncStatus {
+  UpToDate,
+  Pushed,
+  Pulled,
+  Merged,
+}
+
+/// Task created on both sides with the same id, which is given a new id.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct RenamedTask {
+  pub from: TaskId,
+  pub to: TaskId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[typeshare]
+pub struct SyncSummary {
+  pub status: SyncStatus,
+  pub renamed: Vec<RenamedTask>,
+  /// Events which can not be applied after merge, e.g. updates of a task deleted on other side.
+  pub dropped: u32,
+}
+
+impl SyncSummary {
+  pub fn new(status: SyncStatus) -> Self {
+    Self {
+      status,
+      renamed: Vec::new(),
+      dropped: 0,
+    }
+  }
+}
+
+/// `[event]` commit to merge.
+#[derive(Debug, Clone)]
+pub struct EventCommit {
+  pub oid: Oid,
+  /// Commit time in seconds.
+  pub time: i64,
+  pub events: Vec<Persisted<TaskEvent>>,
+  /// The first commit this one is rewritten from, or itself.
+  pub origin: Oid,
+}
+
+impl EventCommit {
+  /// Reads `[event]` commits from `tip` back to (excluding) `hide`, oldest first.
+  pub fn read(repo: &Repository, tip: Oid, hide: Option<Oid>) -> Result<Vec<Self>, Error> {
+    let reader = CommitReader::new(repo)?.start_on_oid(tip);
+    let reader = match hide {
+      Some(hide) => reader.hide_oid(hide),
+      None => reader,
+    };
+    let mut commits = Vec::new();
+    for commit in reader {
+      let commit = commit?;
+      if !commit.message.subject.contains(EVENT_MSG) {
+        continue;
+      }
+      let origin = serde_json::from_str::<EventCommitBody<IgnoredAny>>(commit.message.body.trim())
+        .ok()
+        .and_then(|x| x.origin().and_then(|x| Oid::from_str(x).ok()))
+        .unwrap_or(commit.id);
+      commits.push(Self {
+        oid: commit.id,
+        time: commit.time,
+        origin,
+        events: GitEventstore::commit_to_events(commit)?,
+      });
+    }
+    commits.reverse();
+    Ok(commits)
+  }
+
+  /// When the first event of the commit occurred, which is more precise than commit time.
+  fn occurred_at(&self) -> Timestamp {
+    let first = self.events.iter().map(|x| x.occurred_at).min();
+    first.unwrap_or(self.time * 1000)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Side {
+  Local,
+  Remote,
+}
+
+/// Commit to write on the merged history, with events renamed and renumbered.
+#[derive(Debug, Clone)]
+pub struct MergedCommit {
+  pub source: Oid,
+  pub origin: Oid,
+  pub events: Vec<Persisted<TaskEvent>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Merge {
+  /// Number of leading remote commits which are kept as they are.
+  pub kept: usize,
+  /// Commits to write after the kept ones, in occurrence order.
+  pub commits: Vec<MergedCommit>,
+  pub tasks: AggregateRoot<Task>,
+  pub renamed: Vec<RenamedTask>,
+  pub dropped: u32,
+}
+
+/// Replays commits of both sides on `tasks` in order of their events' occurrence time, remote
+/// first on a tie. Commits of each side keep their order even if their times go backwards,
+/// e.g. with clock skew between machines, so kept remote commits stay a prefix of `remote`.
+///
+/// Task created with a number which is already taken is given the next free one, and later
+/// events of the same side follow the new number. Such a task keyed by its number, written
+/// before tasks had unique ids, is also given a uid so it is not mistaken for the other one.
+pub fn merge_commits(
+  tasks: AggregateRoot<Task>,
+  local: Vec<EventCommit>,
+  remote: Vec<EventCommit>,
+) -> Merge {
+  let mut remote = remote.into_iter().peekable();
+  let mut local = local.into_iter().peekable();
+  let mut commits = Vec::new();
+  loop {
+    let side = match (remote.peek(), local.peek()) {
+      (Some(r), Some(l)) if r.occurred_at() <= l.occurred_at() => Side::Remote,
+      (Some(_), Some(_)) | (None, Some(_)) => Side::Local,
+      (Some(_), None) => Side::Remote,
+      (None, None) => break,
+    };
+    let commit = match side {
+      Side::Remote => remote.next(),
+      Side::Local => local.next(),
+    };
+    commits.extend(commit.map(|x| (side, x)));
+  }
+  let kept = commits
+    .iter()
+    .take_while(|(side, _)| *side == Side::Remote)
+    .count();
+
+  let mut merge = Merge {
+    kept,
+    commits: Vec::new(),
+    tasks,
+    renamed: Vec::new(),
+    dropped: 0,
+  };
+  let mut renames = Renames::default();
+  for (i, (side, commit)) in commits.into_iter().enumerate() {
+    let events: Vec<_> = commit
+      .events
+      .into_iter()
+      .filter_map(|x| merge.apply(&mut renames, side, x))
+      .collect();
+    if i >= kept {
+      merge.commits.push(MergedCommit {
+        source: commit.oid,
+        origin: commit.origin,
+        events,
+      });
+    }
+  }
+  merge
+}
+
+#[derive(Default)]
+struct Renames {
+  /// Aggregate ids of tasks given a uid.
+  keys: HashMap<(Side, String), TaskUid>,
+  numbers: HashMap<(Side, i32), TaskId>,
+}
+
+impl Merge {
+  fn apply(
+    &mut self,
+    renames: &mut Renames,
+    side: Side,
+    persisted: Persisted<TaskEvent>,
+  ) -> Option<Persisted<TaskEvent>> {
+    let original = persisted.aggregate_id;
+    let mut uid = None;
+    if let Some((no, legacy)) = created_task(&persisted.event) {
+      renames.keys.remove(&(side, original.to_owned()));
+      renames.numbers.remove(&(side, no.no));
+      if self.tasks.get_state(no.to_string()).is_some() {
+        let renamed = get_next_task_id(&self.tasks);
+        renames.numbers.insert((side, no.no), renamed);
+        self.renamed.push(RenamedTask {
+          from: no,
+          to: renamed,
+        });
+        if legacy {
+          let generated = TaskUid::generate();
+          renames
+            .keys
+            .insert((side, original.to_owned()), generated.to_owned());
+          uid = Some(generated);
+        }
+      }
+    }
+    let id = renames
+      .keys
+      .get(&(side, original.to_owned()))
+      .map(|x| x.to_string())
+      .unwrap_or(original);
+    let event = persisted
+      .event
+      .map_task_ids(|x| renames.numbers.get(&(side, x.no)).cloned().unwrap_or(x))
+      .map_task_keys(|x| {
+        renames
+          .keys
+          .get(&(side, x.to_string()))
+          .cloned()
+          .unwrap_or(x)
+      });
+    let event = match uid {
+      Some(uid) => assign_uid(event, uid),
+      None => event,
+    };
+    let version = self.tasks.get_version(&id).cloned().unwrap_or(0) + 1;
+    let persisted = Persisted {
+      aggregate_id: id,
+      version,
+      occurred_at: persisted.occurred_at,
+      event,
+    };
+    match self.tasks.save_events(vec![persisted.clone()]) {
+      Ok(()) => Some(persisted),
+      Err(_) => {
+        self.dropped += 1;
+        None
+      }
+    }
+  }
+}
+
+/// Number of the task the event creates, with whether it has no uid.
+fn created_task(event: &TaskEvent) -> Option<(TaskId, bool)> {
+  match event {
+    TaskEvent::Created { uid, id, .. } => Some((*id, uid.is_none())),
+    TaskEvent::Restored { task } => Some((task.id, task.uid.is_none())),
+    _ => None,
+  }
+}
+
+fn assign_uid(event: TaskEvent, uid: TaskUid) -> TaskEvent {
+  match event {
+    TaskEvent::Created {
+      id,
+      title,
+      body,
+      status,
+      schedule,
+      ..
+    } => TaskEvent::Created {
+      uid: Some(uid),
+      id,
+      title,
+      body,
+      status,
+      schedule,
+    },
+    TaskEvent::Restored { mut task } => {
+      task.uid = Some(uid);
+      TaskEvent::Restored { task }
+    }
+    event => event,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::domain::task::TaskStatus;
+
+  use super::*;
+
+  /// Created event keyed by number, as written before tasks had uids.
+  fn created(id: i32, title: &str) -> Persisted<TaskEvent> {
+    Persisted {
+      aggregate_id: TaskId::new(id).to_string(),
+      version: 1,
+      occurred_at: 0,
+      event: TaskEvent::Created {
+        uid: None,
+        id: TaskId::new(id),
+        title: title.to_string(),
+        body: None,
+        status: TaskStatus::Backlog,
+        schedule: None,
+      },
+    }
+  }
+
+  fn created_with_uid(uid: &TaskUid, id: i32, title: &str) -> Persisted<TaskEvent> {
+    let mut persisted = created(id, title);
+    persisted.aggregate_id = uid.to_string();
+    persisted.event = assign_uid(persisted.event, uid.to_owned());
+    persisted
+  }
+
+  /// Commit of `events` occurred at `at` seconds.
+  fn commit(no: u8, at: i64, events: Vec<Persisted<TaskEvent>>) -> EventCommit {
+    commit_at(no, at * 1000, events)
+  }
+
+  fn commit_at(no: u8, at: Timestamp, events: Vec<Persisted<TaskEvent>>) -> EventCommit {
+    let oid = Oid::from_bytes(&[no; 20]).unwrap();
+    let events = events
+      .into_iter()
+      .map(|x| Persisted {
+        occurred_at: at,
+        ..x
+      })
+      .collect();
+    EventCommit {
+      oid,
+      time: at / 1000,
+      events,
+      origin: oid,
+    }
+  }
+
+  fn titles(tasks: &AggregateRoot<Task>) -> Vec<(i32, String)> {
+    let mut titles: Vec<_> = tasks
+      .states
+      .values()
+      .map(|x| (x.id.no, x.title.to_owned()))
+      .collect();
+    titles.sort();
+    titles
+  }
+
+  #[test]
+  fn rename_later_created_task() {
+    let (local_uid, remote_uid) = (TaskUid::generate(), TaskUid::generate());
+    let local = vec![
+      commit(1, 10, vec![created_with_uid(&local_uid, 1, "Local")]),
+      commit(
+        2,
+        30,
+        vec![Persisted {
+          aggregate_id: local_uid.to_string(),
+          version: 2,
+          occurred_at: 0,
+          event: TaskEvent::TitleUpdated {
+            title: "Local updated".to_string(),
+          },
+        }],
+      ),
+    ];
+    let remote = vec![commit(
+      3,
+      20,
+      vec![created_with_uid(&remote_uid, 1, "Remote")],
+    )];
+
+    let merge = merge_commits(AggregateRoot::default(), local, remote);
+
+    assert_eq!(merge.kept, 0);
+    let sources: Vec<_> = merge.commits.iter().map(|x| x.source).collect();
+    assert_eq!(
+      sources,
+      vec![
+        Oid::from_bytes(&[1; 20]).unwrap(),
+        Oid::from_bytes(&[3; 20]).unwrap(),
+        Oid::from_bytes(&[2; 20]).unwrap(),
+      ]
+    );
+    assert_eq!(
+      merge.renamed,
+      vec![RenamedTask {
+        from: TaskId::new(1),
+        to: TaskId::new(2),
+      }]
+    );
+    // only the number changes, events are still keyed by uid.
+    assert_eq!(
+      merge.commits[1].events[0].aggregate_id,
+      remote_uid.to_string()
+    );
+    assert_eq!(
+      titles(&merge.tasks),
+      vec![(1, "Local updated".to_string()), (2, "Remote".to_string())]
+    );
+    assert_eq!(merge.tasks.get_version("#1"), Some(&2));
+    assert_eq!(merge.tasks.get_state("#2").unwrap().uid, Some(remote_uid));
+  }
+
+  #[test]
+  fn give_uid_to_renamed_legacy_task() {
+    let local = vec![commit(1, 10, vec![created(1, "Local")])];
+    let remote = vec![
+      commit(2, 20, vec![created(1, "Remote")]),
+      commit(
+        3,
+        30,
+        vec![Persisted {
+          aggregate_id: "#1".to_string(),
+          version: 2,
+          occurred_at: 0,
+          event: TaskEvent::TitleUpdated {
+            title: "Remote updated".to_string(),
+          },
+        }],
+      ),
+    ];
+
+    let merge = merge_commits(AggregateRoot::default(), local, remote);
+
+    let uid = merge.tasks.get_state("#2").unwrap().uid.clone().unwrap();
+    let ids: Vec<_> = merge
+      .commits
+      .iter()
+      .map(|x| x.events[0].aggregate_id.to_owned())
+      .collect();
+    assert_eq!(
+      ids,
+      vec!["#1".to_string(), uid.to_string(), uid.to_string()]
+    );
+    assert_eq!(
+      titles(&merge.tasks),
+      vec![(1, "Local".to_string()), (2, "Remote updated".to_string())]
+    );
+  }
+
+  #[test]
+  fn order_commits_of_the_same_second_by_occurrence() {
+    let title = |title: &str| Persisted {
+      aggregate_id: "#1".to_string(),
+      version: 2,
+      occurred_at: 0,
+      event: TaskEvent::TitleUpdated {
+        title: title.to_string(),
+      },
+    };
+    let mut tasks = AggregateRoot::default();
+    tasks.save_events(vec![created(1, "Base")]).unwrap();
+    let local = vec![commit_at(1, 10_100, vec![title("Local")])];
+    let remote = vec![commit_at(2, 10_900, vec![title("Remote")])];
+
+    let merge = merge_commits(tasks, local, remote);
+
+    assert_eq!(merge.kept, 0);
+    assert_eq!(titles(&merge.tasks), vec![(1, "Remote".to_string())]);
+  }
+
+  #[test]
+  fn keep_order_of_each_side_with_clock_skew() {
+    let mut tasks = AggregateRoot::default();
+    tasks.save_events(vec![created(1, "Base")]).unwrap();
+    let title = |version: u64, title: &str| Persisted {
+      aggregate_id: "#1".to_string(),
+      version,
+      occurred_at: 0,
+      event: TaskEvent::TitleUpdated {
+        title: title.to_string(),
+      },
+    };
+    // the second remote commit is written on a machine whose clock is behind.
+    let remote = vec![
+      commit(1, 20, vec![title(2, "Remote")]),
+      commit(2, 10, vec![title(3, "Remote skewed")]),
+    ];
+    let local = vec![commit(3, 15, vec![title(2, "Local")])];
+
+    let merge = merge_commits(tasks, local, remote);
+
+    assert_eq!(merge.kept, 0);
+    let sources: Vec<_> = merge.commits.iter().map(|x| x.source).collect();
+    assert_eq!(
+      sources,
+      vec![
+        Oid::from_bytes(&[3; 20]).unwrap(),
+        Oid::from_bytes(&[1; 20]).unwrap(),
+        Oid::from_bytes(&[2; 20]).unwrap(),
+      ]
+    );
+    assert_eq!(titles(&merge.tasks), vec![(1, "Remote skewed".to_string())]);
+  }
+
+  #[test]
+  fn keep_older_remote_commits() {
+    let mut tasks = AggregateRoot::default();
+    tasks.save_events(vec![created(1, "Base")]).unwrap();
+    let remote = vec![
+      commit(1, 10, vec![created(2, "Remote")]),
+      commit(
+        2,
+        30,
+        vec![Persisted {
+          aggregate_id: "#1".to_string(),
+          version: 2,
+          occurred_at: 0,
+          event: TaskEvent::Deleted {},
+        }],
+      ),
+    ];
+    let local = vec![commit(
+      3,
+      20,
+      vec![Persisted {
+        aggregate_id: "#1".to_string(),
+        version: 2,
+        occurred_at: 0,
+        event: TaskEvent::TitleUpdated {
+          title: "Local".to_string(),
+        },
+      }],
+    )];
+
+    let merge = merge_commits(tasks, local, remote);
+
+    assert_eq!(merge.kept, 1);
+    assert_eq!(merge.commits.len(), 2);
+    // deleted after the local update, so nothing is dropped.
+    assert_eq!(merge.dropped, 0);
+    assert_eq!(merge.commits[1].events[0].version, 3);
+    assert_eq!(titles(&merge.tasks), vec![(2, "Remote".to_string())]);
+  }
+}
@@ -0,0 +1,7 @@
+pub use error::*;
+pub use merge::*;
+pub use remote::*;
+
+mod error;
+mod merge;
+mod remote;
@@ -0,0 +1,95 @@
+use std::cell::{Cell, RefCell};
+
+use git2::build::CheckoutBuilder;
+use git2::{Oid, PushOptions, RemoteCallbacks, Repository};
+
+use crate::sync::Error;
+
+pub const REMOTE_NAME: &str = "origin";
+
+/// Adds the remote to sync with, or changes its url.
+pub fn set_remote(repo: &Repository, url: &str) -> Result<(), Error> {
+  match repo.find_remote(REMOTE_NAME) {
+    Ok(_) => repo.remote_set_url(REMOTE_NAME, url)?,
+    Err(_) => {
+      repo.remote(REMOTE_NAME, url)?;
+    }
+  };
+  Ok(())
+}
+
+pub fn current_branch(repo: &Repository) -> Result<String, Error> {
+  let head = repo.head()?;
+  let branch = head.shorthand().unwrap_or("master");
+  Ok(branch.to_string())
+}
+
+/// Fetches the branch of remote, returns its head or `None` when remote does not have it yet.
+pub fn fetch(repo: &Repository, branch: &str) -> Result<Option<Oid>, Error> {
+  let mut remote = repo.find_remote(REMOTE_NAME).map_err(|_| Error::NoRemote)?;
+  let tracking = tracking_ref(branch);
+  let refspec = format!("+refs/heads/{}:{}", branch, tracking);
+  remote.fetch(&[refspec], None, None)?;
+  Ok(repo.refname_to_id(&tracking).ok())
+}
+
+/// Pushes the branch. When `expected` is given, remote should be still there, so rewritten
+/// history never drops commits pushed by others meanwhile.
+pub fn push(
+  repo: &Repository,
+  branch: &str,
+  force: bool,
+  expected: Option<Oid>,
+) -> Result<(), Error> {
+  let mut remote = repo.find_remote(REMOTE_NAME).map_err(|_| Error::NoRemote)?;
+  let refname = format!("refs/heads/{}", branch);
+  let changed = Cell::new(false);
+  let rejected = RefCell::new(None);
+  let mut callbacks = RemoteCallbacks::new();
+  callbacks.push_negotiation(|updates| {
+    // `src` is where the remote is now, zero when it does not have the branch.
+    let current = updates
+      .iter()
+      .find(|x| x.dst_refname() == Some(&refname))
+      .map(|x| x.src())
+      .filter(|x| !x.is_zero());
+    if current != expected {
+      changed.set(true);
+      return Err(git2::Error::from_str("remote is changed"));
+    }
+    Ok(())
+  });
+  callbacks.push_update_reference(|_, status| {
+    *rejected.borrow_mut() = status.map(|x| x.to_string());
+    Ok(())
+  });
+  let mut options = PushOptions::new();
+  options.remote_callbacks(callbacks);
+
+  let refspec = format!("{}{}:{}", if force { "+" } else { "" }, refname, refname);
+  let result = remote.push(&[refspec], Some(&mut options));
+  if changed.get() {
+    return Err(Error::RemoteChanged);
+  }
+  drop(options);
+  result?;
+  if let Some(message) = rejected.into_inner() {
+    return Err(Error::Rejected(message));
+  }
+  let head = repo.refname_to_id(&refname)?;
+  repo.reference(&tracking_ref(branch), head, true, "sync: push")?;
+  Ok(())
+}
+
+/// Moves the branch to `oid` and checks it out, dropping changes of working dir.
+pub fn reset_branch(repo: &Repository, branch: &str, oid: Oid) -> Result<(), Error> {
+  let tree = repo.find_commit(oid)?.tree()?;
+  // checkout before moving the branch, so files only in the current tree are removed.
+  repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().force()))?;
+  repo.reference(&format!("refs/heads/{}", branch), oid, true, "sync: reset")?;
+  Ok(())
+}
+
+fn tracking_ref(branch: &str) -> String {
+  format!("refs/remotes/{}/{}", REMOTE_NAME, branch)
+}
@@ -0,0 +1,220 @@
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const DELIMITER: &str = "---";
+const BOM: char = '\u{feff}';
+
+#[derive(thiserror::Error, Debug)]
+#[error("{}:{line}: {source}", path.display())]
+pub struct FrontmatterError {
+  pub path: PathBuf,
+  /// Line of the error in the file, or of the opening delimiter when yaml does not tell.
+  pub line: usize,
+  pub source: serde_yaml::Error,
+}
+
+/// Parses frontmatter of file `path` and returns it with the content after it.
+/// Frontmatter is `None` when the text does not start with one.
+pub fn parse_frontmatter<'a, T>(
+  path: &Path,
+  text: &'a str,
+) -> Result<(Option<T>, &'a str), FrontmatterError>
+where
+  T: DeserializeOwned,
+{
+  let (frontmatter, content) = split_frontmatter(text);
+  let Some(frontmatter) = frontmatter else {
+    return Ok((None, content));
+  };
+  let data = serde_yaml::from_str::<T>(frontmatter).map_err(|source| FrontmatterError {
+    path: path.to_path_buf(),
+    // yaml starts on the line after the opening delimiter.
+    line: source.location().map(|x| x.line() + 1).unwrap_or(1),
+    source,
+  })?;
+  Ok((Some(data), content))
+}
+
+/// Splits yaml between `---` lines at the start of the text from the content after them.
+/// A leading BOM and CRLF line breaks are accepted. Frontmatter is `None` when the text does
+/// not start with a delimiter or it is never closed, and the whole text is content then.
+pub fn split_frontmatter(text: &str) -> (Option<&str>, &str) {
+  let unmarked = text.strip_prefix(BOM).unwrap_or(text);
+  let Some(start) = delimiter_end(unmarked, 0) else {
+    return (None, text);
+  };
+  let mut offset = start;
+  while offset < unmarked.len() {
+    if let Some(end) = delimiter_end(unmarked, offset) {
+      return (Some(&unmarked[start..offset]), &unmarked[end..]);
+    }
+    offset = match unmarked[offset..].find('\n') {
+      Some(x) => offset + x + 1,
+      None => unmarked.len(),
+    };
+  }
+  (None, text)
+}
+
+/// Writes `data` as frontmatter followed by `content`.
+pub fn write_frontmatter<T>(data: &T, content: &str) -> Result<String, serde_yaml::Error>
+where
+  T: Serialize,
+{
+  let yaml = serde_yaml::to_string(data)?;
+  Ok(format!("{DELIMITER}\n{yaml}{DELIMITER}\n{content}"))
+}
+
+/// End of the delimiter line starting at `offset` with its line break, when it is one.
+fn delimiter_end(text: &str, offset: usize) -> Option<usize> {
+  let rest = text[offset..].strip_prefix(DELIMITER)?;
+  let line = rest.split_inclusive('\n').next().unwrap_or("");
+  line
+    .trim_end()
+    .is_empty()
+    .then_some(offset + DELIMITER.len() + line.len())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use serde::{Deserialize, Serialize};
+
+  #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+  struct Metadata {
+    id: u64,
+    data: Data,
+  }
+
+  #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+  struct Data {
+    value: String,
+  }
+
+  #[test]
+  fn parse() {
+    let text = "---\nid: 1\ndata: {\"value\":\"Hello World\"}\n---\nThis is contents.";
+    let (metadata, content) = parse_frontmatter::<Metadata>(Path::new("a.md"), text).unwrap();
+
+    assert_eq!(
+      metadata,
+      Some(Metadata {
+        id: 1,
+        data: Data {
+          value: String::from("Hello World")
+        }
+      })
+    );
+    assert_eq!(content, "This is contents.");
+  }
+
+  #[test]
+  fn report_path_and_line() {
+    let text = "---\nid: 1\ndata: [\n---\n";
+    let e = parse_frontmatter::<Metadata>(Path::new("tasks/#1.md"), text).unwrap_err();
+    assert_eq!(e.path, Path::new("tasks/#1.md"));
+    assert_eq!(e.line, 3);
+    assert!(e.to_string().starts_with("tasks/#1.md:3: "));
+  }
+
+  #[test]
+  fn split() {
+    let text = "---\nid: 1\n---\n\n## Title\nHello\n";
+    assert_eq!(
+      split_frontmatter(text),
+      (Some("id: 1\n"), "\n## Title\nHello\n")
+    );
+    assert_eq!(
+      split_frontmatter("## Title\nHello"),
+      (None, "## Title\nHello")
+    );
+    assert_eq!(split_frontmatter("---\nid: 1"), (None, "---\nid: 1"));
+    assert_eq!(split_frontmatter("---\nid: 1\n---"), (Some("id: 1\n"), ""));
+    assert_eq!(split_frontmatter("---\n---\nbody"), (Some(""), "body"));
+    assert_eq!(
+      split_frontmatter("\u{feff}---\r\nid: 1\r\n---\r\nbody\r\n"),
+      (Some("id: 1\r\n"), "body\r\n")
+    );
+    // only a block at the start is frontmatter.
+    assert_eq!(
+      split_frontmatter("\n---\nid: 1\n---\n"),
+      (None, "\n---\nid: 1\n---\n")
+    );
+    // horizontal rules in content are kept.
+    assert_eq!(
+      split_frontmatter("---\nid: 1\n---\nabove\n---\nbelow\n---\n"),
+      (Some("id: 1\n"), "above\n---\nbelow\n---\n")
+    );
+    assert_eq!(
+      split_frontmatter("---\nid: ----\n----\n--- \nbody"),
+      (Some("id: ----\n----\n"), "body")
+    );
+  }
+
+  /// Pieces of texts close to frontmatter, which are joined at random.
+  const PIECES: [&str; 14] = [
+    "---",
+    "---\n",
+    "---\r\n",
+    "--- \n",
+    "----\n",
+    "\n",
+    "\r\n",
+    "\u{feff}",
+    "id: 1\n",
+    "a: b",
+    "# Title\n",
+    "한글 ",
+    "...\n",
+    "- x\n",
+  ];
+
+  /// Deterministic random texts, so failures are reproducible.
+  fn texts(count: usize) -> impl Iterator<Item = String> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..count).map(move |_| {
+      let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize
+      };
+      let len = next() % 12;
+      (0..len).map(|_| PIECES[next() % PIECES.len()]).collect()
+    })
+  }
+
+  #[test]
+  fn split_random_texts() {
+    for text in texts(10000) {
+      let (frontmatter, content) = split_frontmatter(&text);
+      match frontmatter {
+        Some(frontmatter) => {
+          let unmarked = text.strip_prefix(BOM).unwrap_or(&text);
+          assert!(unmarked.starts_with(DELIMITER), "{:?}", text);
+          assert!(unmarked.ends_with(content), "{:?}", text);
+          assert!(unmarked[3..].contains(frontmatter), "{:?}", text);
+        }
+        None => assert_eq!(content, text),
+      }
+    }
+  }
+
+  #[test]
+  fn write_and_parse_random_contents() {
+    for (i, content) in texts(1000).enumerate() {
+      let metadata = Metadata {
+        id: i as u64,
+        data: Data {
+          value: content.to_owned(),
+        },
+      };
+      let text = write_frontmatter(&metadata, &content).unwrap();
+      let parsed = parse_frontmatter::<Metadata>(Path::new("a.md"), &text).unwrap();
+      assert_eq!(parsed, (Some(metadata), content.as_str()), "{:?}", text);
+    }
+  }
+}
@@ -0,0 +1,3 @@
+pub use frontmatter::*;
+
+mod frontmatter;
@@ -0,0 +1,487 @@
+use std::collections::HashSet;
+use std::{fs, path};
+
+use git2::{Oid, Repository};
+
+use crate::domain::task::{Task, TaskEvent};
+use crate::eventsourcing::git::{
+  commit_snapshot, force_commit_snapshot, GitEventstore, GitEventstoreError,
+};
+use crate::eventsourcing::{
+  rebuild, AggregateRoot, Eventstore, LoadAggregateError, Persisted, Snapshot,
+};
+use crate::git;
+use crate::git::{commit, get_head, CommitReader};
+use crate::snapshots::{diff_snapshot, SnapshotDiff, TaskSnapshot};
+use crate::sync;
+use crate::sync::{
+  current_branch, fetch, merge_commits, push, reset_branch, EventCommit, MergedCommit, SyncStatus,
+  SyncSummary,
+};
+
+pub type LoadTasksError =
+  LoadAggregateError<crate::domain::Error, GitEventstoreError, crate::snapshots::Error>;
+
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct Workspace {
+  dir: path::PathBuf,
+}
+
+impl Workspace {
+  pub fn new(basedir: &path::Path) -> Result<Self, git::Error> {
+    Self::open(&basedir.join("workspace"))
+  }
+
+  /// Opens workspace on `dir`, creates new one when not exists.
+  pub fn open(dir: &path::Path) -> Result<Self, git::Error> {
+    if fs::read_dir(dir).is_err() {
+      fs::create_dir_all(dir)?;
+    }
+    let repo = match Repository::open(dir) {
+      Ok(repo) => repo,
+      Err(_) => Repository::init(dir)?,
+    };
+    if get_head(&repo).is_err() {
+      commit(&repo, "initial")?;
+    }
+    Ok(Self {
+      dir: dir.to_path_buf(),
+    })
+  }
+
+  pub fn path(&self) -> &path::Path {
+    &self.dir
+  }
+
+  pub fn repo(&self) -> Result<Repository, git::Error> {
+    let repo = Repository::open(self.path())?;
+    Ok(repo)
+  }
+
+  /// Loads tasks from snapshot and replays events committed after the snapshot.
+  /// Nothing is written, see `save_snapshot` to make next load start from here.
+  pub async fn load_tasks(&self) -> Result<AggregateRoot<Task>, LoadTasksError> {
+    let eventstore = GitEventstore::new(self.path());
+    let snapshot = TaskSnapshot::new(self.path())
+      .await
+      .map_err(LoadAggregateError::Snapshot)?;
+
+    let mut root = snapshot
+      .load()
+      .await
+      .map_err(LoadAggregateError::Snapshot)?;
+    let unsaved_events = eventstore
+      .read_until_snapshot()
+      .await
+      .map_err(LoadAggregateError::Eventstore)?;
+
+    root
+      .save_events(unsaved_in_files(&root, unsaved_events))
+      .map_err(LoadAggregateError::Aggregate)?;
+
+    Ok(root)
+  }
+
+  /// Saves task files from `root` and commits them, so next load starts from here.
+  pub async fn save_snapshot(&self, root: &AggregateRoot<Task>) -> Result<(), LoadTasksError> {
+    TaskSnapshot::new(self.path())
+      .await
+      .map_err(LoadAggregateError::Snapshot)?
+      .save(root)
+      .await
+      .map_err(LoadAggregateError::Snapshot)?;
+    let repo = self
+      .repo()
+      .map_err(|e| LoadAggregateError::Eventstore(e.into()))?;
+    commit_snapshot(&repo).map_err(|e| LoadAggregateError::Eventstore(e.into()))?;
+    Ok(())
+  }
+
+  /// Compares tasks loaded from snapshot with tasks replayed from the first event.
+  /// Nothing is written, so differences are still there to be repaired.
+  pub async fn verify(&self) -> Result<Vec<SnapshotDiff>, LoadTasksError> {
+    let eventstore = GitEventstore::new(self.path());
+    let mut loaded = TaskSnapshot::new(self.path())
+      .await
+      .map_err(LoadAggregateError::Snapshot)?
+      .load()
+      .await
+      .map_err(LoadAggregateError::Snapshot)?;
+    let unsaved_events = eventstore
+      .read_until_snapshot()
+      .await
+      .map_err(LoadAggregateError::Eventstore)?;
+    loaded
+      .save_events(unsaved_in_files(&loaded, unsaved_events))
+      .map_err(LoadAggregateError::Aggregate)?;
+
+    let replayed = self.replay_tasks().await?;
+    Ok(diff_snapshot(&loaded, &replayed))
+  }
+
+  /// Regenerates snapshot files from all events and commits them as a fresh snapshot.
+  pub async fn repair(&self) -> Result<AggregateRoot<Task>, LoadTasksError> {
+    let snapshot = TaskSnapshot::new(self.path())
+      .await
+      .map_err(LoadAggregateError::Snapshot)?;
+    let root = rebuild(&GitEventstore::new(self.path()), &snapshot).await?;
+    let repo = self
+      .repo()
+      .map_err(|e| LoadAggregateError::Eventstore(e.into()))?;
+    force_commit_snapshot(&repo).map_err(|e| LoadAggregateError::Eventstore(e.into()))?;
+
+    Ok(root)
+  }
+
+  async fn replay_tasks(&self) -> Result<AggregateRoot<Task>, LoadTasksError> {
+    let events = GitEventstore::new(self.path())
+      .read_all()
+      .await
+      .map_err(LoadAggregateError::Eventstore)?;
+    let mut root = AggregateRoot::default();
+    root
+      .save_events(events)
+      .map_err(LoadAggregateError::Aggregate)?;
+    Ok(root)
+  }
+
+  /// Sets url of the remote repository to sync with.
+  pub fn set_remote(&self, url: &str) -> Result<(), sync::Error> {
+    sync::set_remote(&self.repo()?, url)
+  }
+
+  /// Pulls and pushes `[event]` commits with the remote.
+  ///
+  /// Diverged commits are interleaved in occurrence order from the merge base, keeping the
+  /// order of each side. Leading remote commits older than any local one are kept, so only the
+  /// rest is rewritten and force pushed.
+  /// Snapshot is saved from the replayed tasks, never from either side.
+  pub async fn sync(&self) -> Result<SyncSummary, sync::Error> {
+    let repo = self.repo()?;
+    commit_snapshot(&repo)?;
+    let branch = current_branch(&repo)?;
+    let local = get_head(&repo)?;
+    let Some(remote) = fetch(&repo, &branch)? else {
+      push(&repo, &branch, false, None)?;
+      return Ok(SyncSummary::new(SyncStatus::Pushed));
+    };
+    if local == remote {
+      return Ok(SyncSummary::new(SyncStatus::UpToDate));
+    }
+    if repo.graph_descendant_of(local, remote)? {
+      push(&repo, &branch, false, Some(remote))?;
+      return Ok(SyncSummary::new(SyncStatus::Pushed));
+    }
+
+    // histories of workspaces created on each machine have no common commit.
+    let base = repo.merge_base(local, remote).ok();
+    let remote_commits = EventCommit::read(&repo, remote, base)?;
+    let merged: HashSet<_> = remote_commits.iter().map(|x| x.origin).collect();
+    let local_commits: Vec<_> = EventCommit::read(&repo, local, base)?
+      .into_iter()
+      .filter(|x| !merged.contains(&x.origin))
+      .collect();
+    if local_commits.is_empty() {
+      reset_branch(&repo, &branch, remote)?;
+      return Ok(SyncSummary::new(SyncStatus::Pulled));
+    }
+
+    let start = match base {
+      Some(base) => base,
+      None => root_commit(&repo, remote)?,
+    };
+    let mut tasks = AggregateRoot::default();
+    let events = EventCommit::read(&repo, start, None)?
+      .into_iter()
+      .flat_map(|x| x.events)
+      .collect();
+    tasks.save_events(events)?;
+
+    let kept_count = remote_commits.len();
+    let merge = merge_commits(tasks, local_commits, remote_commits.clone());
+    let mut head = match merge.kept {
+      x if x == kept_count => remote,
+      0 => start,
+      x => remote_commits[x - 1].oid,
+    };
+    for merged in &merge.commits {
+      if !merged.events.is_empty() {
+        head = commit_merged(&repo, head, merged)?;
+      }
+    }
+    reset_branch(&repo, &branch, head)?;
+    TaskSnapshot::new(self.path())
+      .await?
+      .save(&merge.tasks)
+      .await?;
+    commit_snapshot(&repo)?;
+    push(&repo, &branch, merge.kept < kept_count, Some(remote))?;
+
+    Ok(SyncSummary {
+      status: SyncStatus::Merged,
+      renamed: merge.renamed,
+      dropped: merge.dropped,
+    })
+  }
+}
+
+/// Events not written to task files yet. Files may be ahead of the last snapshot commit, as the
+/// app writes them after each command without committing. A task without a file is replayed
+/// only from its first event, otherwise it was deleted after the file was written.
+fn unsaved_in_files(
+  root: &AggregateRoot<Task>,
+  events: Vec<Persisted<TaskEvent>>,
+) -> Vec<Persisted<TaskEvent>> {
+  let mut created = HashSet::new();
+  events
+    .into_iter()
+    .filter(|x| match root.versions.get(&x.aggregate_id) {
+      Some(version) => x.version > *version,
+      None => {
+        if x.version == 1 {
+          created.insert(x.aggregate_id.to_owned());
+        }
+        created.contains(&x.aggregate_id)
+      }
+    })
+    .collect()
+}
+
+/// Writes merged commit on `parent`, keeping author and time of the source commit.
+fn commit_merged(repo: &Repository, parent: Oid, merged: &MergedCommit) -> Result<Oid, git::Error> {
+  let source = repo.find_commit(merged.source)?;
+  let parent = repo.find_commit(parent)?;
+  let message = GitEventstore::<TaskEvent>::events_to_commit_message(
+    merged.events.clone(),
+    Some(merged.origin),
+  );
+  let oid = repo.commit(
+    None,
+    &source.author(),
+    &source.committer(),
+    &message.to_string(),
+    &parent.tree()?,
+    &[&parent],
+  )?;
+  Ok(oid)
+}
+
+fn root_commit(repo: &Repository, oid: Oid) -> Result<Oid, git::Error> {
+  let mut root = oid;
+  for commit in CommitReader::new(repo)?.start_on_oid(oid) {
+    root = commit?.id;
+  }
+  Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+  use geeks_tracker_testing::git::FixtureRepository;
+  use geeks_tracker_testing::tempdir::TempDir;
+
+  use crate::domain::task::{TaskCommand, TaskId};
+  use crate::eventsourcing::git::SNAPSHOT_MSG;
+  use crate::git::get_head_commit;
+  use crate::sync::RenamedTask;
+
+  use super::*;
+
+  async fn create_task(workspace: &Workspace, title: &str) {
+    let mut tasks = workspace.load_tasks().await.unwrap();
+    let persisted = tasks
+      .execute_command(TaskCommand::Create {
+        title: title.to_string(),
+        status: None,
+        schedule: None,
+      })
+      .unwrap();
+    GitEventstore::new(workspace.path())
+      .append(vec![persisted])
+      .await
+      .unwrap();
+  }
+
+  async fn titles(workspace: &Workspace) -> Vec<(String, String)> {
+    let tasks = workspace.load_tasks().await.unwrap();
+    let mut titles: Vec<_> = tasks
+      .states
+      .values()
+      .map(|x| (x.id.to_string(), x.title.to_owned()))
+      .collect();
+    titles.sort();
+    titles
+  }
+
+  #[tokio::test]
+  async fn load_tasks_from_events() {
+    let fixture = FixtureRepository::default();
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    let mut tasks = workspace.load_tasks().await.unwrap();
+    let persisted = tasks
+      .execute_command(TaskCommand::Create {
+        title: "Hello".to_string(),
+        status: None,
+        schedule: None,
+      })
+      .unwrap();
+    GitEventstore::new(workspace.path())
+      .append(vec![persisted])
+      .await
+      .unwrap();
+
+    let tasks = workspace.load_tasks().await.unwrap();
+    assert_eq!(tasks.get_state("#1").unwrap().title, "Hello");
+    assert!(!workspace.path().join("tasks/#1.md").exists());
+    workspace.save_snapshot(&tasks).await.unwrap();
+    assert!(workspace.path().join("tasks/#1.md").exists());
+
+    let tasks = workspace.load_tasks().await.unwrap();
+    assert_eq!(tasks.get_version("#1"), Some(&1));
+  }
+
+  #[tokio::test]
+  async fn load_task_files_ahead_of_snapshot_commit() {
+    let fixture = FixtureRepository::default();
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    create_task(&workspace, "Hello").await;
+    create_task(&workspace, "World").await;
+    let mut tasks = workspace.load_tasks().await.unwrap();
+
+    // as the app does, files are written without a snapshot commit.
+    let events = [
+      TaskCommand::UpdateTitle {
+        id: TaskId::new(1),
+        title: "Hello again".to_string(),
+      },
+      TaskCommand::Delete { id: TaskId::new(2) },
+    ]
+    .into_iter()
+    .map(|x| tasks.execute_command(x).unwrap())
+    .collect::<Vec<_>>();
+    GitEventstore::new(workspace.path())
+      .append(events.clone())
+      .await
+      .unwrap();
+    TaskSnapshot::new(workspace.path())
+      .await
+      .unwrap()
+      .save_tasks(&tasks, events.iter().map(|x| x.aggregate_id.as_str()))
+      .await
+      .unwrap();
+
+    assert_eq!(
+      titles(&workspace).await,
+      vec![("#1".to_string(), "Hello again".to_string())]
+    );
+    assert_eq!(
+      workspace.load_tasks().await.unwrap().get_version("#1"),
+      Some(&2)
+    );
+    assert!(workspace.verify().await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn load_without_writing() {
+    let fixture = FixtureRepository::default();
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    create_task(&workspace, "Hello").await;
+    let tasks = workspace.load_tasks().await.unwrap();
+    workspace.save_snapshot(&tasks).await.unwrap();
+    let path = workspace.path().join("tasks/milk.md");
+    fs::write(&path, "# Buy milk\n").unwrap();
+    let head = get_head_commit(&workspace.repo().unwrap()).unwrap().id;
+
+    // new files are left to the watcher.
+    assert_eq!(
+      titles(&workspace).await,
+      vec![("#1".to_string(), "Hello".to_string())]
+    );
+    assert!(path.exists());
+    assert_eq!(
+      get_head_commit(&workspace.repo().unwrap()).unwrap().id,
+      head
+    );
+  }
+
+  #[tokio::test]
+  async fn verify_and_repair_snapshot() {
+    let fixture = FixtureRepository::default();
+    let workspace = Workspace::open(fixture.path()).unwrap();
+    create_task(&workspace, "Hello").await;
+    create_task(&workspace, "World").await;
+    let tasks = workspace.load_tasks().await.unwrap();
+    workspace.save_snapshot(&tasks).await.unwrap();
+    assert!(workspace.verify().await.unwrap().is_empty());
+
+    let path = workspace.path().join("tasks/#1.md");
+    let raw = fs::read_to_string(&path).unwrap();
+    fs::write(&path, raw.replace("title: Hello", "title: Edited")).unwrap();
+    fs::remove_file(workspace.path().join("tasks/#2.md")).unwrap();
+    // head is a snapshot commit, so the broken files would be kept by load_tasks.
+    commit_snapshot(&workspace.repo().unwrap()).unwrap();
+    let tasks = workspace.replay_tasks().await.unwrap();
+    let id = |no: &str| tasks.resolve_id(no).to_string();
+    let diffs = workspace.verify().await.unwrap();
+    assert_eq!(diffs.len(), 2);
+    assert!(diffs.contains(&SnapshotDiff::StateMismatch { id: id("#1") }));
+    assert!(diffs.contains(&SnapshotDiff::Missing { id: id("#2") }));
+
+    workspace.repair().await.unwrap();
+    assert!(workspace.verify().await.unwrap().is_empty());
+    assert_eq!(
+      titles(&workspace).await,
+      vec![
+        ("#1".to_string(), "Hello".to_string()),
+        ("#2".to_string(), "World".to_string()),
+      ]
+    );
+    let head = get_head_commit(&workspace.repo().unwrap()).unwrap();
+    assert!(head.message.subject.contains(SNAPSHOT_MSG));
+  }
+
+  #[tokio::test]
+  async fn sync_with_remote() {
+    let remote = TempDir::new("remote").unwrap();
+    Repository::init_bare(remote.path()).unwrap();
+    let url = remote.path().to_str().unwrap();
+    let (fixture_a, fixture_b) = (FixtureRepository::default(), FixtureRepository::default());
+    let a = Workspace::open(fixture_a.path()).unwrap();
+    let b = Workspace::open(fixture_b.path()).unwrap();
+    a.set_remote(url).unwrap();
+    b.set_remote(url).unwrap();
+
+    create_task(&a, "First").await;
+    assert_eq!(a.sync().await.unwrap().status, SyncStatus::Pushed);
+    assert_eq!(b.sync().await.unwrap().status, SyncStatus::Pulled);
+    assert_eq!(b.sync().await.unwrap().status, SyncStatus::UpToDate);
+    assert_eq!(
+      titles(&b).await,
+      vec![("#1".to_string(), "First".to_string())]
+    );
+
+    // both sides create #2 meanwhile, the one pushed first keeps it.
+    create_task(&a, "From A").await;
+    create_task(&b, "From B").await;
+    assert_eq!(a.sync().await.unwrap().status, SyncStatus::Pushed);
+    let summary = b.sync().await.unwrap();
+    assert_eq!(summary.status, SyncStatus::Merged);
+    assert_eq!(
+      summary.renamed,
+      vec![RenamedTask {
+        from: TaskId::new(2),
+        to: TaskId::new(3),
+      }]
+    );
+    assert!(b.path().join("tasks/#3.md").exists());
+    assert_eq!(a.sync().await.unwrap().status, SyncStatus::Pulled);
+
+    let expected = vec![
+      ("#1".to_string(), "First".to_string()),
+      ("#2".to_string(), "From A".to_string()),
+      ("#3".to_string(), "From B".to_string()),
+    ];
+    assert_eq!(titles(&a).await, expected);
+    assert_eq!(titles(&b).await, expected);
+  }
+}
@@ -4,6 +4,12 @@
 
 export type TaskId = string;
 
+/**
+ * Unique id of a task, which never changes unlike `TaskId`.
+ * Tasks created before uids were introduced are keyed by their number `#n` in its place.
+ */
+export type TaskUid = string;
+
 export type Version = number;
 
 export type Timestamp = number;
@@ -15,12 +21,125 @@ export enum TaskStatus {
 	Done = "done",
 }
 
+export enum ReportFormat {
+	Markdown = "markdown",
+	Json = "json",
+}
+
+export enum TaskSortKey {
+	Id = "id",
+	Title = "title",
+	Status = "status",
+	CreatedAt = "created_at",
+	UpdatedAt = "updated_at",
+}
+
+export enum SortDirection {
+	Asc = "asc",
+	Desc = "desc",
+}
+
+export enum SyncStatus {
+	UpToDate = "up_to_date",
+	Pushed = "pushed",
+	Pulled = "pulled",
+	Merged = "merged",
+}
+
+export enum Weekday {
+	Monday = "monday",
+	Tuesday = "tuesday",
+	Wednesday = "wednesday",
+	Thursday = "thursday",
+	Friday = "friday",
+	Saturday = "saturday",
+	Sunday = "sunday",
+}
+
+/** Filter, sort and page of tasks. Every given condition should match. */
+export interface TaskQuery {
+	/** Any of the statuses. */
+	status?: TaskStatus[];
+	/** Case-insensitive match in title or body. */
+	text?: string;
+	tag?: string;
+	/** `from` inclusive, `to` exclusive. */
+	createdFrom?: Timestamp;
+	createdTo?: Timestamp;
+	updatedFrom?: Timestamp;
+	updatedTo?: Timestamp;
+	hasSchedule?: boolean;
+	sort?: TaskSortKey;
+	direction?: SortDirection;
+	limit?: number;
+	offset?: number;
+}
+
 export interface TaskSchedule {
 	at: Timestamp;
 	status: TaskStatus;
+	repeat?: Recurrence;
+}
+
+/** A step of the task, `id` is unique only in the task. */
+export interface ChecklistItem {
+	id: number;
+	text: string;
+	checked: boolean;
+}
+
+export interface ChecklistProgress {
+	done: number;
+	total: number;
+}
+
+/** A period the task stayed in `TaskStatus::InProgress`. */
+export interface WorkSession {
+	startedAt: Timestamp;
+	endedAt?: Timestamp;
+}
+
+export interface ReportedTask {
+	id: string;
+	title: string;
+}
+
+export interface DailyReport {
+	/** `YYYY-MM-DD` in the offset the report was built with. */
+	date: string;
+	created: ReportedTask[];
+	started: ReportedTask[];
+	completed: ReportedTask[];
+	/** Tasks still in progress at the end of the day. */
+	inProgress: ReportedTask[];
+}
+
+/** Summary of task activity between `from` (inclusive) and `to` (exclusive), grouped by day. */
+export interface Report {
+	from: Timestamp;
+	to: Timestamp;
+	days: DailyReport[];
+}
+
+/** Task created on both sides with the same id, which is given a new id. */
+export interface RenamedTask {
+	from: TaskId;
+	to: TaskId;
+}
+
+export interface SyncSummary {
+	status: SyncStatus;
+	renamed: RenamedTask[];
+	/** Events which can not be applied after merge, e.g. updates of a task deleted on other side. */
+	dropped: number;
 }
 
 export interface Task {
+	/**
+	 * Aggregate id of the task when set, and `id` is only an alias which may be reassigned.
+	 * Tasks created before it was introduced don't have one, and keep `id` as aggregate id.
+	 */
+	uid?: TaskUid;
 	id: TaskId;
 	title: string;
 	body?: string;
@@ -32,21 +151,73 @@ export interface Task {
 	queueAt?: Timestamp;
 	doneAt?: Timestamp;
 	schedule?: TaskSchedule;
+	tags: string[];
+	sessions: WorkSession[];
+	/** Tasks which must be done before this task starts, by aggregate id. */
+	blockedBy: TaskUid[];
+	checklist: ChecklistItem[];
 }
 
 export interface Persisted<T> {
 	aggregateId: string;
 	version: Version;
+	/** When the command was executed (milliseconds), so replays never depend on the clock. */
+	occurredAt: Timestamp;
 	event: T;
 }
 
+/** Event of a task with the commit it is stored in, and the task right before and after it. */
+export interface TaskHistoryEntry {
+	persisted: Persisted<TaskEvent>;
+	oid: string;
+	authorName: string;
+	authorEmail: string;
+	/** Commit time (milliseconds). */
+	committedAt: Timestamp;
+	/** `None` when the task did not exist, e.g. before it is created or after it is deleted. */
+	before?: Task;
+	after?: Task;
+}
+
+/** Rule of recurring schedule. */
+export type Recurrence = 
+	/** Every `every` milliseconds. */
+	| { name: "interval", data: {
+	every: Timestamp;
+}}
+	/** On `weekdays` (every day when empty) at `hour:minute` of local time. */
+	| { name: "weekly", data: {
+	weekdays: Weekday[];
+	hour: number;
+	minute: number;
+}};
+
 export type DispatchMessage = 
 	| { name: "task.persisted", data: {
 	events: Persisted<TaskEvent>[];
+	canUndo: boolean;
+	canRedo: boolean;
+}}
+	| { name: "task.undone", data: {
+	events: Persisted<TaskEvent>[];
+	canUndo: boolean;
+	canRedo: boolean;
+}}
+	| { name: "task.redone", data: {
+	events: Persisted<TaskEvent>[];
+	canUndo: boolean;
+	canRedo: boolean;
+}}
+	| { name: "workspace.synced", data: {
+	summary: SyncSummary;
+	canUndo: boolean;
+	canRedo: boolean;
 }};
 
 export type TaskEvent = 
 	| { name: "task.created", data: {
+	/** `None` in events written before tasks had unique ids. */
+	uid?: TaskUid;
 	id: TaskId;
 	title: string;
 	body?: string;
@@ -66,6 +237,45 @@ export type TaskEvent =
 }}
 	| { name: "task.scheduleUpdated", data: {
 	schedule?: TaskSchedule;
+}}
+	| { name: "task.tagAdded", data: {
+	tag: string;
+}}
+	| { name: "task.tagRemoved", data: {
+	tag: string;
+}}
+	| { name: "task.blockerAdded", data: {
+	blocker: TaskUid;
+}}
+	| { name: "task.blockerRemoved", data: {
+	blocker: TaskUid;
+}}
+	| { name: "task.checklistItemAdded", data: {
+	item: ChecklistItem;
+	position: number;
+}}
+	| { name: "task.checklistItemToggled", data: {
+	item: number;
+	checked: boolean;
+}}
+	| { name: "task.checklistItemMoved", data: {
+	item: number;
+	position: number;
+}}
+	| { name: "task.checklistItemRemoved", data: {
+	item: number;
+}}
+	| { name: "task.restored", data: {
+	task: Task;
+}}
+	/**
+	 * Status changed by schedule, `scheduled_at` is kept even if it fired late (e.g. after downtime).
+	 * When it fired is the occurrence time of the event.
+	 */
+	| { name: "task.scheduleFired", data: {
+	status: TaskStatus;
+	scheduledAt: Timestamp;
+	next?: TaskSchedule;
 }};
 
 export type TaskCommand = 
@@ -81,6 +291,7 @@ export type TaskCommand =
 	| { name: "task.updateStatus", data: {
 	id: TaskId;
 	status: TaskStatus;
+	force: boolean;
 }}
 	| { name: "task.updateBody", data: {
 	id: TaskId;
@@ -92,5 +303,53 @@ export type TaskCommand =
 	| { name: "task.updateSchedule", data: {
 	id: TaskId;
 	schedule?: TaskSchedule;
+}}
+	| { name: "task.addTag", data: {
+	id: TaskId;
+	tag: string;
+}}
+	| { name: "task.removeTag", data: {
+	id: TaskId;
+	tag: string;
+}}
+	| { name: "task.addBlocker", data: {
+	id: TaskId;
+	blocker: TaskId;
+}}
+	| { name: "task.removeBlocker", data: {
+	id: TaskId;
+	blocker: TaskId;
+}}
+	/** Appends the item unless `position` is given. */
+	| { name: "task.addChecklistItem", data: {
+	id: TaskId;
+	text: string;
+	checked: boolean;
+	position?: number;
+}}
+	| { name: "task.toggleChecklistItem", data: {
+	id: TaskId;
+	item: number;
+}}
+	| { name: "task.moveChecklistItem", data: {
+	id: TaskId;
+	item: number;
+	position: number;
+}}
+	| { name: "task.removeChecklistItem", data: {
+	id: TaskId;
+	item: number;
+}}
+	| { name: "task.restore", data: {
+	task: Task;
+}}
+	/**
+	 * Applies due schedule, replacing it with `next` one.
+	 * Skipped with `expected_version` when the task changed after the schedule was read.
+	 */
+	| { name: "task.fireSchedule", data: {
+	id: TaskId;
+	next?: TaskSchedule;
+	expectedVersion?: Version;
 }};
 
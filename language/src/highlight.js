@@ -6,9 +6,13 @@ export const highlighting = styleTags({
   delete: tags.keyword,
   title: tags.keyword,
   status: tags.keyword,
+  every: tags.keyword,
+  at: tags.keyword,
   Number: tags.number,
   String: tags.string,
   TaskId: tags.name,
   TaskStatus: tags.literal,
   Duration: tags.literal,
+  Weekday: tags.literal,
+  Time: tags.literal,
 });
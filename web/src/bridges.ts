@@ -1,4 +1,12 @@
-import type { Command, Task, TaskStatus } from '@geeks-tracker/core';
+import type {
+  ChecklistProgress,
+  Command,
+  ReportFormat,
+  SyncSummary,
+  Task,
+  TaskHistoryEntry,
+  TaskQuery,
+} from '@geeks-tracker/core';
 import { invoke } from '@tauri-apps/api';
 import { emit } from '@tauri-apps/api/event';
 
@@ -6,15 +14,13 @@ export function hideApp() {
   return emit('hide_app');
 }
 
-export interface ListTasksFilter {
-  keyword?: string;
-  status?: TaskStatus[];
-  from?: string;
-  to?: string;
-}
+export type ListedTask = Task & {
+  progress?: ChecklistProgress;
+};
 
-export function listTasks(filter?: ListTasksFilter) {
-  return invoke<Task[]>('list_tasks', { filter });
+/** `search` is like `status:in_progress "deploy" created:>2024-05-01`, ranked by relevance. */
+export function listTasks(query?: TaskQuery, search?: string) {
+  return invoke<ListedTask[]>('list_tasks', { query, search });
 }
 
 export function getTask(id: string) {
@@ -25,3 +31,24 @@ export function runCommand(command: Command) {
   const [category] = command.name.split('.');
   return invoke<void>(`run_${category}_command`, { command });
 }
+
+export function undoTask(count?: number) {
+  return invoke<void>('undo_task', { count });
+}
+
+export function redoTask(count?: number) {
+  return invoke<void>('redo_task', { count });
+}
+
+export function syncWorkspace() {
+  return invoke<SyncSummary>('sync_workspace');
+}
+
+export function generateReport(from: number, to: number, format?: ReportFormat) {
+  return invoke<string>('generate_report', { from, to, format });
+}
+
+/** Events of the task with before and after states, oldest first. */
+export function taskHistory(id: string) {
+  return invoke<TaskHistoryEntry[]>('task_history', { id });
+}
@@ -1,9 +1,10 @@
-import { type Task, type TaskStatus, formatTaskStatus } from '@geeks-tracker/core';
+import { type TaskStatus, formatTaskStatus } from '@geeks-tracker/core';
 import { useSuspenseQuery } from '@tanstack/react-query';
 import { groupBy, objectEntries } from '@toss/utils';
 import cx from 'classnames';
 import { useSubscription } from 'observable-hooks';
 import { useMemo, useState } from 'react';
+import type { ListedTask } from '../bridges';
 import { keyDowns$ } from '../global';
 import { ChevronDownIcon } from '../icons/ChevronDownIcon';
 import { ChevronUpIcon } from '../icons/ChevronUpIcon';
@@ -89,7 +90,7 @@ export function Tasks() {
 interface TaskGroupProps {
   expanded: boolean;
   status: TaskStatus;
-  tasks: Task[];
+  tasks: ListedTask[];
   selected: string | undefined;
   onExpandChange: (expanded: boolean) => void;
 }
@@ -127,7 +128,7 @@ function TaskGroup({ expanded, status, selected, onExpandChange, tasks }: TaskGr
 }
 
 interface TaskItemProps {
-  task: Task;
+  task: ListedTask;
   selected?: boolean;
   className?: string;
 }
@@ -140,6 +141,11 @@ function TaskItem({ task, selected, className }: TaskItemProps) {
     >
       <span className="w-8">{task.id}</span>
       <span className="flex-1 text-ellipsis overflow-hidden min-w-0">{task.title}</span>
+      {task.progress != null ? (
+        <span className="text-xs text-zinc-400">
+          {task.progress.done}/{task.progress.total}
+        </span>
+      ) : null}
       {task.schedule != null ? <ClockIcon size={12} /> : null}
     </li>
   );
@@ -1,12 +1,58 @@
 import { HighlightStyle, LRLanguage, LanguageSupport, syntaxHighlighting } from '@codemirror/language';
 import { EditorSelection, EditorState } from '@codemirror/state';
 import { drawSelection, highlightSpecialChars, keymap, placeholder } from '@codemirror/view';
-import type { Command, TaskStatus } from '@geeks-tracker/core';
+import type { Command, Recurrence, TaskSchedule, TaskStatus } from '@geeks-tracker/core';
 import { tags } from '@lezer/highlight';
 import { EditorView } from 'codemirror';
 import { parser } from '../../language';
 import { runCommand } from './bridges';
 import ms, { type StringValue } from './ms';
+import { WEEKDAYS, nextOccurrence, parseWeekday } from './recurrence';
+
+type SyntaxNode = ReturnType<typeof parser.parse>['topNode'];
+
+function parseRepeat(text: string, repeatNode: SyntaxNode | null): Recurrence | undefined {
+  if (repeatNode == null) {
+    return undefined;
+  }
+  const durationNode = repeatNode.getChild('Duration');
+  if (durationNode != null) {
+    return { name: 'interval', data: { every: ms(text.slice(durationNode.from, durationNode.to) as StringValue) } };
+  }
+  const timeNode = repeatNode.getChild('At')?.getChild('Time');
+  const [hour = 0, minute = 0] = timeNode != null ? text.slice(timeNode.from, timeNode.to).split(':').map(Number) : [];
+  const [, keyword] = text.slice(repeatNode.from, repeatNode.to).split(/\s+/);
+  const weekdays =
+    keyword === 'weekday'
+      ? WEEKDAYS
+      : repeatNode
+          .getChildren('Weekday')
+          .map(x => parseWeekday(text.slice(x.from, x.to))!)
+          .filter((x, i, list) => list.indexOf(x) === i);
+  return { name: 'weekly', data: { weekdays, hour, minute } };
+}
+
+function parseSchedule(
+  text: string,
+  withStatusNode: SyntaxNode | null | undefined,
+  status: TaskStatus | undefined
+): TaskSchedule | undefined {
+  if (withStatusNode == null || status == null) {
+    return undefined;
+  }
+  const durationNode = withStatusNode.getChild('Duration');
+  const duration = durationNode != null ? ms(text.slice(durationNode.from, durationNode.to) as StringValue) : undefined;
+  const repeat = parseRepeat(text, withStatusNode.getChild('Repeat'));
+  const now = Date.now();
+  if (duration != null) {
+    return { at: now + duration, status, repeat };
+  }
+  if (repeat != null) {
+    const at = nextOccurrence(repeat, now, now);
+    return at != null ? { at, status, repeat } : undefined;
+  }
+  return undefined;
+}
 
 function parseCommand(text: string): Command | null {
   try {
@@ -21,19 +67,14 @@ function parseCommand(text: string): Command | null {
             const withStatusNode = ref.node.getChild('WithStatus');
             const statusNode = withStatusNode?.getChild('TaskStatus');
             const status = statusNode != null ? (text.slice(statusNode.from, statusNode.to) as TaskStatus) : undefined;
-            const durationNode = withStatusNode?.getChild('Duration');
-            const duration =
-              durationNode != null ? ms(text.slice(durationNode.from, durationNode.to) as StringValue) : undefined;
+            const schedule = parseSchedule(text, withStatusNode, status);
             command =
-              status != null && duration != null
+              schedule != null
                 ? {
                     name: 'task.create',
                     data: {
                       title,
-                      schedule: {
-                        at: Date.now() + duration,
-                        status,
-                      },
+                      schedule,
                     },
                   }
                 : {
@@ -65,23 +106,14 @@ function parseCommand(text: string): Command | null {
               if (withStatusNode != null) {
                 const statusNode = withStatusNode.getChild('TaskStatus')!;
                 const status = text.slice(statusNode.from, statusNode.to) as TaskStatus;
-                const durationNode = withStatusNode.getChild('Duration');
-                const duration =
-                  durationNode != null ? ms(text.slice(durationNode.from, durationNode.to) as StringValue) : undefined;
-                console.log(
-                  durationNode != null ? text.slice(durationNode.from, durationNode.to) : undefined,
-                  duration
-                );
+                const schedule = parseSchedule(text, withStatusNode, status);
                 command =
-                  duration != null
+                  schedule != null
                     ? {
                         name: 'task.updateSchedule',
                         data: {
                           id: taskId,
-                          schedule: {
-                            at: Date.now() + duration,
-                            status,
-                          },
+                          schedule,
                         },
                       }
                     : {
@@ -89,6 +121,7 @@ function parseCommand(text: string): Command | null {
                         data: {
                           id: taskId,
                           status,
+                          force: false,
                         },
                       };
               }
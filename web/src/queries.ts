@@ -1,10 +1,11 @@
-import { type ListTasksFilter, getTask, listTasks } from './bridges';
+import type { TaskQuery } from '@geeks-tracker/core';
+import { getTask, listTasks } from './bridges';
 
 export const taskQueries = {
   all: ['tasks'],
-  list: (filter: ListTasksFilter = {}) => ({
-    queryKey: ['tasks', 'list', { filter }],
-    queryFn: () => listTasks(filter),
+  list: (query: TaskQuery = {}, search?: string) => ({
+    queryKey: ['tasks', 'list', { query, search }],
+    queryFn: () => listTasks(query, search),
   }),
   detail: (id: string) => ({
     queryKey: ['tasks', 'detail', id],
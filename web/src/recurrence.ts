@@ -0,0 +1,55 @@
+import { type Recurrence, Weekday } from '@geeks-tracker/core';
+
+export const WEEKDAYS: Weekday[] = [
+  Weekday.Monday,
+  Weekday.Tuesday,
+  Weekday.Wednesday,
+  Weekday.Thursday,
+  Weekday.Friday,
+];
+
+// Ordered by `Date.prototype.getDay()`.
+const DAYS_OF_WEEK: Weekday[] = [
+  Weekday.Sunday,
+  Weekday.Monday,
+  Weekday.Tuesday,
+  Weekday.Wednesday,
+  Weekday.Thursday,
+  Weekday.Friday,
+  Weekday.Saturday,
+];
+
+export function parseWeekday(text: string): Weekday | undefined {
+  return DAYS_OF_WEEK.find(x => x === text || x.slice(0, 3) === text);
+}
+
+/** Same as `Recurrence::next` in core, in local time. */
+export function nextOccurrence(repeat: Recurrence, at: number, now: number): number | undefined {
+  switch (repeat.name) {
+    case 'interval': {
+      const { every } = repeat.data;
+      if (every <= 0) {
+        return undefined;
+      }
+      const skipped = Math.floor(Math.max(now - at, 0) / every);
+      return at + (skipped + 1) * every;
+    }
+    case 'weekly': {
+      const { weekdays, hour, minute } = repeat.data;
+      const after = Math.max(at, now);
+      for (let i = 0; i <= 7; i += 1) {
+        const date = new Date(after);
+        date.setDate(date.getDate() + i);
+        date.setHours(hour, minute, 0, 0);
+        const weekday = DAYS_OF_WEEK[date.getDay()]!;
+        if (weekdays.length > 0 && !weekdays.includes(weekday)) {
+          continue;
+        }
+        if (date.getTime() > after) {
+          return date.getTime();
+        }
+      }
+      return undefined;
+    }
+  }
+}
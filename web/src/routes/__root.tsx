@@ -49,6 +49,9 @@ function Root() {
   useSubscription(dispatcherMessages$, e => {
     switch (e.payload.name) {
       case 'task.persisted':
+      case 'task.undone':
+      case 'task.redone':
+      case 'workspace.synced':
         queryClient.invalidateQueries({ queryKey: taskQueries.all });
         break;
     }